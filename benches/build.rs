@@ -0,0 +1,42 @@
+//! Compares building a balanced tree with `from_sorted_slice` and with
+//! `par_from_sorted_slice`.
+//!
+//! Run with `cargo bench --bench build --features rayon`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use binary_tree::BinaryTree;
+
+const LEN: u64 = 1 << 22;
+const ROUNDS: usize = 5;
+
+/// Runs `f` `ROUNDS` times and returns the fastest round.
+fn best_of(mut f: impl FnMut()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let items: Vec<(u64, u64)> = (0..LEN).map(|key| (key, key)).collect();
+
+    let sequential = best_of(|| {
+        black_box(BinaryTree::from_sorted_slice(black_box(&items)));
+    });
+
+    let parallel = best_of(|| {
+        black_box(BinaryTree::par_from_sorted_slice(black_box(&items)));
+    });
+
+    let millis = |total: Duration| total.as_secs_f64() * 1e3;
+
+    println!("threads               {:>8}", rayon::current_num_threads());
+    println!("from_sorted_slice     {:>8.2} ms", millis(sequential));
+    println!("par_from_sorted_slice {:>8.2} ms", millis(parallel));
+}
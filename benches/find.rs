@@ -0,0 +1,65 @@
+//! Compares the branchy lookup of `get` with the branchless `get_fast` and
+//! the batched `get_many`.
+//!
+//! Run with `cargo bench --bench find`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use binary_tree::BinaryTree;
+
+const LEN: u64 = 1 << 20;
+const LOOKUPS: u64 = 1 << 22;
+const ROUNDS: usize = 5;
+
+/// Runs `f` `ROUNDS` times and returns the fastest round.
+fn best_of(mut f: impl FnMut()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+/// A cheap xorshift stream of keys, so both variants see the same lookups.
+fn keys() -> impl Iterator<Item = u64> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+
+    (0..LOOKUPS).map(move |_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state % (2 * LEN)
+    })
+}
+
+fn main() {
+    let b_tree: BinaryTree<u64, u64> = (0..LEN).map(|key| (key * 2, key)).collect();
+
+    let branchy = best_of(|| {
+        for key in keys() {
+            black_box(b_tree.get(black_box(&key)));
+        }
+    });
+
+    let branchless = best_of(|| {
+        for key in keys() {
+            black_box(b_tree.get_fast(black_box(&key)));
+        }
+    });
+
+    let batch: Vec<u64> = keys().collect();
+
+    let batched = best_of(|| {
+        black_box(b_tree.get_many(black_box(&batch)));
+    });
+
+    let per_lookup = |total: Duration| total.as_nanos() as f64 / LOOKUPS as f64;
+
+    println!("get      {:>8.2} ns/lookup", per_lookup(branchy));
+    println!("get_fast {:>8.2} ns/lookup", per_lookup(branchless));
+    println!("get_many {:>8.2} ns/lookup", per_lookup(batched));
+}
@@ -0,0 +1,183 @@
+use core::marker::PhantomData;
+
+use crate::{BinaryTree, Natural};
+
+/// The settings a BinaryTree keeps across rebuilds.
+///
+/// The ratios are stored as `f32`, which is plenty for them and keeps every
+/// tree a word smaller.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Config {
+    /// The factor `grow` multiplies the capacity by.
+    pub(crate) growth_factor: f32,
+    /// Whether the capacity is fixed, so that inserts never allocate.
+    pub(crate) fixed: bool,
+    /// The occupancy below which `remove_and_maybe_shrink` compacts.
+    pub(crate) shrink_threshold: f32,
+}
+
+impl Config {
+    /// The capacity to grow to from `capacity`.
+    ///
+    /// Rounded up to a whole cell and at least one cell larger.
+    pub(crate) fn grown_capacity(&self, capacity: usize) -> usize {
+        let grown = capacity as f64 * f64::from(self.growth_factor);
+        let mut new_capacity = grown as usize;
+
+        if (new_capacity as f64) < grown {
+            new_capacity += 1;
+        }
+
+        new_capacity.max(capacity + 1)
+    }
+
+    /// Whether `len` entries in `capacity` cells fall below the shrink threshold.
+    pub(crate) fn should_shrink(&self, len: usize, capacity: usize) -> bool {
+        (len as f64) < capacity as f64 * f64::from(self.shrink_threshold)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            growth_factor: 2.0,
+            fixed: false,
+            shrink_threshold: 0.25,
+        }
+    }
+}
+
+/// Configures a BinaryTree before creating it.
+///
+/// Constructed by [`BinaryTree::builder`].
+pub struct Builder<K, V, C = Natural> {
+    capacity: usize,
+    cmp: C,
+    config: Config,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> Builder<K, V> {
+    pub(crate) fn new() -> Self {
+        Self {
+            capacity: 8,
+            cmp: Natural,
+            config: Config::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V, C> Builder<K, V, C> {
+    /// The number of cells to allocate up front.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Orders the keys by `cmp` instead of their `Ord` implementation.
+    pub fn comparator<D>(self, cmp: D) -> Builder<K, V, D> {
+        Builder {
+            capacity: self.capacity,
+            cmp,
+            config: self.config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The factor the capacity is multiplied by whenever the tree runs out
+    /// of cells, 2 by default.
+    ///
+    /// The array fills level by level, and each level is as large as all
+    /// levels above it together. Doubling therefore adds exactly one level,
+    /// while smaller factors add part of one and save memory at the cost of
+    /// growing more often. The new capacity is rounded up to a whole cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is not greater than 1.
+    pub fn growth_factor(mut self, factor: f64) -> Self {
+        assert!(factor > 1.0, "growth factor must be greater than 1");
+
+        self.config.growth_factor = factor as f32;
+        self
+    }
+
+    /// The fraction of occupied cells below which
+    /// [`BinaryTree::remove_and_maybe_shrink`] compacts the array, 0.25 by
+    /// default. A threshold of 0 never compacts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is not in `0.0..1.0`.
+    pub fn shrink_threshold(mut self, threshold: f64) -> Self {
+        assert!(
+            (0.0..1.0).contains(&threshold),
+            "shrink threshold must be in 0.0..1.0"
+        );
+
+        self.config.shrink_threshold = threshold as f32;
+        self
+    }
+
+    pub fn build(self) -> BinaryTree<K, V, C> {
+        let mut tree = BinaryTree::with_capacity_and_comparator(self.capacity, self.cmp);
+        tree.config = self.config;
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryTree;
+
+    #[test]
+    fn growth_factor() {
+        let mut b_tree = BinaryTree::builder().capacity(4).growth_factor(1.5).build();
+        let mut capacities = vec![b_tree.capacity()];
+
+        for key in 0..200 {
+            b_tree.insert(key * 37 % 200, key);
+            capacities.push(b_tree.capacity());
+        }
+
+        assert!(capacities.windows(2).all(|w| w[0] <= w[1]));
+        assert!(capacities
+            .iter()
+            .any(|capacity| !capacity.is_power_of_two()));
+        assert_eq!(b_tree.len(), 200);
+        assert!(b_tree.keys().copied().eq(0..200));
+        assert_eq!(b_tree.get(&37), Some(&1));
+    }
+
+    #[test]
+    fn shrink_threshold() {
+        let mut eager = BinaryTree::builder().shrink_threshold(0.9).build();
+        let mut never = BinaryTree::builder().shrink_threshold(0.0).build();
+
+        for key in 0..100 {
+            eager.insert(key, ());
+            never.insert(key, ());
+        }
+
+        let capacity = never.capacity();
+
+        eager.remove_and_maybe_shrink(&0);
+        never.remove_and_maybe_shrink(&0);
+
+        assert!(eager.capacity() < capacity);
+        assert_eq!(never.capacity(), capacity);
+    }
+
+    #[test]
+    #[should_panic(expected = "shrink threshold must be in 0.0..1.0")]
+    fn shrink_threshold_in_range() {
+        BinaryTree::<i32, i32>::builder().shrink_threshold(1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "growth factor must be greater than 1")]
+    fn growth_factor_must_grow() {
+        BinaryTree::<i32, i32>::builder().growth_factor(1.0);
+    }
+}
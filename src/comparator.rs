@@ -0,0 +1,27 @@
+use core::cmp::Ordering;
+
+/// Decides the order of the keys in a BinaryTree.
+///
+/// Implemented for [`Natural`] and for every `Fn(&K, &K) -> Ordering`.
+pub trait Comparator<K: ?Sized> {
+    fn compare(&self, a: &K, b: &K) -> Ordering;
+}
+
+/// Orders keys by their `Ord` implementation. This is the default comparator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Natural;
+
+impl<K: Ord + ?Sized> Comparator<K> for Natural {
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+impl<K: ?Sized, F> Comparator<K> for F
+where
+    F: Fn(&K, &K) -> Ordering,
+{
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        self(a, b)
+    }
+}
@@ -0,0 +1,110 @@
+use std::borrow::Borrow;
+use std::sync::RwLock;
+
+use crate::BinaryTree;
+
+/// A BinaryTree behind a `RwLock`, to share it between threads.
+///
+/// Lookups take a read lock and hand out clones of the values, since
+/// references could not outlive the lock. Mutations take a write lock.
+/// A panic while holding the lock poisons it, so every later call panics.
+pub struct ConcurrentBinaryTree<K, V>(RwLock<BinaryTree<K, V>>);
+
+impl<K: Ord, V> ConcurrentBinaryTree<K, V> {
+    pub fn new() -> Self {
+        Self::from(BinaryTree::new())
+    }
+
+    /// A clone of the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        V: Clone,
+    {
+        self.0.read().expect("lock poisoned").get(key).cloned()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.0.read().expect("lock poisoned").contains_key(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.0.write().expect("lock poisoned").insert(key, value)
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.0.write().expect("lock poisoned").remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.read().expect("lock poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Unwraps the tree.
+    pub fn into_inner(self) -> BinaryTree<K, V> {
+        self.0.into_inner().expect("lock poisoned")
+    }
+}
+
+impl<K: Ord, V> Default for ConcurrentBinaryTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> From<BinaryTree<K, V>> for ConcurrentBinaryTree<K, V> {
+    fn from(tree: BinaryTree<K, V>) -> Self {
+        Self(RwLock::new(tree))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readers_and_a_writer() {
+        let b_tree: ConcurrentBinaryTree<_, _> =
+            BinaryTree::from([(0, 0), (1, 10), (2, 20)]).into();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        assert_eq!(b_tree.get(&1), Some(10));
+                        assert!(b_tree.len() >= 3);
+                    }
+                });
+            }
+
+            scope.spawn(|| {
+                for key in 3..1000 {
+                    assert_eq!(b_tree.insert(key, key * 10), None);
+                }
+
+                assert_eq!(b_tree.remove(&0), Some(0));
+            });
+        });
+
+        assert!(!b_tree.contains_key(&0));
+        assert_eq!(b_tree.len(), 999);
+
+        let b_tree = b_tree.into_inner();
+
+        assert!(b_tree.keys().copied().eq(1..1000));
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+    }
+}
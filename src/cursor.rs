@@ -0,0 +1,187 @@
+use crate::BiTree;
+
+/// A read-only position in a BinaryTree, for walking it by hand.
+///
+/// Constructed by [`BinaryTree::cursor_at_root`](crate::BinaryTree::cursor_at_root).
+/// The moving methods return the entry moved to, or `None` and stay put if
+/// there is no entry in that direction.
+pub struct Cursor<'a, K, V> {
+    pub(crate) mem: &'a [Option<(K, V)>],
+    pub(crate) index: usize,
+}
+
+impl<'a, K, V> Cursor<'a, K, V> {
+    /// The entry at the cursor, or `None` if the tree is empty.
+    pub fn entry(&self) -> Option<(&'a K, &'a V)> {
+        self.mem
+            .get(self.index)
+            .and_then(|cell| cell.as_ref())
+            .map(|(key, value)| (key, value))
+    }
+
+    /// How many edges the cursor is below the root.
+    pub fn depth(&self) -> usize {
+        BiTree::depth(self.index)
+    }
+
+    /// Moves to the left child.
+    pub fn left(&mut self) -> Option<(&'a K, &'a V)> {
+        self.move_to(BiTree::left(self.index)?)
+    }
+
+    /// Moves to the right child.
+    pub fn right(&mut self) -> Option<(&'a K, &'a V)> {
+        self.move_to(BiTree::right(self.index)?)
+    }
+
+    /// Moves to the parent.
+    pub fn parent(&mut self) -> Option<(&'a K, &'a V)> {
+        self.move_to(BiTree::parrent(self.index)?)
+    }
+
+    fn move_to(&mut self, index: usize) -> Option<(&'a K, &'a V)> {
+        let entry = Cursor { index, ..*self }.entry()?;
+        self.index = index;
+
+        Some(entry)
+    }
+}
+
+impl<K, V> Clone for Cursor<'_, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Cursor<'_, K, V> {}
+
+/// A position in a BinaryTree that can change the values it passes.
+///
+/// Constructed by [`BinaryTree::cursor_mut_at_root`](crate::BinaryTree::cursor_mut_at_root).
+/// Moves like [`Cursor`], but every value it hands out borrows the cursor,
+/// so at most one cell is mutably borrowed at a time. Keys stay read-only to
+/// keep the order intact.
+pub struct CursorMut<'a, K, V> {
+    pub(crate) mem: &'a mut [Option<(K, V)>],
+    pub(crate) index: usize,
+}
+
+impl<K, V> CursorMut<'_, K, V> {
+    /// A read-only cursor at the same position.
+    pub fn as_cursor(&self) -> Cursor<'_, K, V> {
+        Cursor {
+            mem: self.mem,
+            index: self.index,
+        }
+    }
+
+    /// The entry at the cursor, or `None` if the tree is empty.
+    pub fn entry(&self) -> Option<(&K, &V)> {
+        self.as_cursor().entry()
+    }
+
+    /// The entry at the cursor with a mutable value, or `None` if the tree is empty.
+    pub fn entry_mut(&mut self) -> Option<(&K, &mut V)> {
+        self.mem
+            .get_mut(self.index)
+            .and_then(|cell| cell.as_mut())
+            .map(|(key, value)| (&*key, value))
+    }
+
+    /// How many edges the cursor is below the root.
+    pub fn depth(&self) -> usize {
+        BiTree::depth(self.index)
+    }
+
+    /// Moves to the left child.
+    pub fn left(&mut self) -> Option<(&K, &mut V)> {
+        self.move_to(BiTree::left(self.index)?)
+    }
+
+    /// Moves to the right child.
+    pub fn right(&mut self) -> Option<(&K, &mut V)> {
+        self.move_to(BiTree::right(self.index)?)
+    }
+
+    /// Moves to the parent.
+    pub fn parent(&mut self) -> Option<(&K, &mut V)> {
+        self.move_to(BiTree::parrent(self.index)?)
+    }
+
+    fn move_to(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        if !matches!(self.mem.get(index), Some(Some(_))) {
+            return None;
+        }
+
+        self.index = index;
+        self.entry_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryTree;
+
+    #[test]
+    fn walk_to_leaf() {
+        let mut b_tree = BinaryTree::new();
+
+        for key in [4, 2, 6, 1, 3, 5, 7] {
+            b_tree.insert(key, key * 10);
+        }
+
+        let mut cursor = b_tree.cursor_at_root();
+
+        assert_eq!(cursor.entry(), Some((&4, &40)));
+        assert_eq!(cursor.parent(), None);
+        assert_eq!(cursor.left(), Some((&2, &20)));
+        assert_eq!(cursor.right(), Some((&3, &30)));
+        assert_eq!(cursor.depth(), 2);
+
+        // A leaf: both moves fail and leave the cursor in place.
+        assert_eq!(cursor.left(), None);
+        assert_eq!(cursor.right(), None);
+        assert_eq!(cursor.entry(), Some((&3, &30)));
+
+        assert_eq!(cursor.parent(), Some((&2, &20)));
+        assert_eq!(cursor.parent(), Some((&4, &40)));
+    }
+
+    #[test]
+    fn mutate_path() {
+        let mut b_tree = BinaryTree::new();
+
+        for key in [4, 2, 6, 1, 3, 5, 7] {
+            b_tree.insert(key, key * 10);
+        }
+
+        let mut cursor = b_tree.cursor_mut_at_root();
+
+        *cursor.entry_mut().unwrap().1 += 1;
+
+        while let Some((_, value)) = cursor.right() {
+            *value += 1;
+        }
+
+        assert_eq!(cursor.entry(), Some((&7, &71)));
+        assert_eq!(cursor.as_cursor().depth(), 2);
+
+        assert!(b_tree.values().copied().eq([10, 20, 30, 41, 50, 61, 71]));
+    }
+
+    #[test]
+    fn empty() {
+        let b_tree = BinaryTree::<i32, i32>::new();
+        let mut cursor = b_tree.cursor_at_root();
+
+        assert_eq!(cursor.entry(), None);
+        assert_eq!(cursor.left(), None);
+        assert_eq!(cursor.parent(), None);
+
+        let mut b_tree = b_tree;
+        let mut cursor = b_tree.cursor_mut_at_root();
+
+        assert_eq!(cursor.entry_mut(), None);
+        assert_eq!(cursor.right(), None);
+    }
+}
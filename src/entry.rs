@@ -0,0 +1,244 @@
+use core::fmt;
+
+use crate::{BinaryTree, Comparator, Index, Natural};
+
+/// A view into a single entry of a BinaryTree, which may be vacant or occupied.
+///
+/// Constructed by [`BinaryTree::entry`].
+pub enum Entry<'a, K, V, C = Natural, I = usize> {
+    Vacant(VacantEntry<'a, K, V, C, I>),
+    Occupied(OccupiedEntry<'a, K, V, C, I>),
+}
+
+/// A vacant entry, remembering the cell `find_index` located for its key.
+pub struct VacantEntry<'a, K, V, C = Natural, I = usize> {
+    pub(crate) tree: &'a mut BinaryTree<K, V, C, I>,
+    pub(crate) key: K,
+    pub(crate) index: usize,
+}
+
+/// An occupied entry.
+pub struct OccupiedEntry<'a, K, V, C = Natural, I = usize> {
+    pub(crate) tree: &'a mut BinaryTree<K, V, C, I>,
+    pub(crate) index: usize,
+}
+
+impl<'a, K, V, C: Comparator<K>, I: Index> Entry<'a, K, V, C, I> {
+    /// Inserts `default` if the entry is vacant and returns the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the result of `default` if the entry is vacant and returns the value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default()),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    /// Inserts `V::default()` if the entry is vacant and returns the value.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(Default::default)
+    }
+
+    /// Calls `f` on the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Vacant(entry) => entry.key(),
+            Entry::Occupied(entry) => entry.key(),
+        }
+    }
+}
+
+impl<'a, K, V, C: Comparator<K>, I: Index> VacantEntry<'a, K, V, C, I> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts `value` and returns a reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let index = self.tree.insert_vacant(self.index, self.key, value);
+
+        OccupiedEntry {
+            tree: self.tree,
+            index,
+        }
+        .into_mut()
+    }
+}
+
+impl<'a, K, V, C, I: Index> OccupiedEntry<'a, K, V, C, I> {
+    fn kv(&self) -> &(K, V) {
+        self.tree.mem[self.index]
+            .as_ref()
+            .expect("OccupiedEntry points to an empty cell")
+    }
+
+    fn kv_mut(&mut self) -> &mut (K, V) {
+        self.tree.mem[self.index]
+            .as_mut()
+            .expect("OccupiedEntry points to an empty cell")
+    }
+
+    pub fn key(&self) -> &K {
+        &self.kv().0
+    }
+
+    pub fn get(&self) -> &V {
+        &self.kv().1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.kv_mut().1
+    }
+
+    /// Converts the entry into a reference to its value with the lifetime of the tree.
+    pub fn into_mut(self) -> &'a mut V {
+        self.tree.mem[self.index]
+            .as_mut()
+            .map(|kv| &mut kv.1)
+            .expect("OccupiedEntry points to an empty cell")
+    }
+
+    /// Replaces the value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the tree, returning its value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Removes the entry from the tree.
+    pub fn remove_entry(self) -> (K, V) {
+        self.tree.remove_at(self.index)
+    }
+}
+
+/// The error returned by [`BinaryTree::try_insert`] when the key is already present.
+///
+/// Carries the rejected key and value along with the existing entry.
+pub struct OccupiedError<'a, K, V, C = Natural, I = usize> {
+    pub entry: OccupiedEntry<'a, K, V, C, I>,
+    pub key: K,
+    pub value: V,
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, C, I: Index> fmt::Debug for OccupiedError<'_, K, V, C, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, C, I: Index> fmt::Display for OccupiedError<'_, K, V, C, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key(),
+            self.entry.get(),
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: fmt::Debug, V: fmt::Debug, C, I: Index> std::error::Error
+    for OccupiedError<'_, K, V, C, I>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting() {
+        let mut counts = BinaryTree::new();
+
+        for word in "a b c a b a d a".split(' ') {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+
+        let vec: Vec<_> = counts.into_iter().collect();
+
+        assert_eq!(vec, vec![("a", 4), ("b", 2), ("c", 1), ("d", 1)]);
+    }
+
+    #[test]
+    fn or_insert_grows() {
+        let mut b_tree = BinaryTree::with_capacity(1);
+
+        for key in 0..100 {
+            *b_tree.entry(key).or_insert_with(|| key) += 1;
+        }
+
+        assert_eq!(b_tree.len(), 100);
+        assert!(b_tree.iter().all(|(key, value)| *value == key + 1));
+    }
+
+    #[test]
+    fn and_modify() {
+        let mut b_tree = BinaryTree::new();
+
+        b_tree.insert(1, 10);
+
+        b_tree.entry(1).and_modify(|v| *v += 1).or_insert(0);
+        b_tree.entry(2).and_modify(|v| *v += 1).or_insert(0);
+
+        assert_eq!(b_tree.get(&1), Some(&11));
+        assert_eq!(b_tree.get(&2), Some(&0));
+    }
+
+    #[test]
+    fn try_insert() {
+        let mut b_tree = BinaryTree::new();
+
+        *b_tree.try_insert(1, "eins").unwrap() = "one";
+
+        let err = b_tree.try_insert(1, "uno").unwrap_err();
+
+        assert_eq!((err.key, err.value), (1, "uno"));
+        assert_eq!(err.entry.get(), &"one");
+        assert_eq!(b_tree.get(&1), Some(&"one"));
+        assert_eq!(b_tree.len(), 1);
+    }
+
+    #[test]
+    fn occupied_entry() {
+        let mut b_tree: BinaryTree<_, _> = (1..=7).map(|key| (key, key)).collect();
+
+        match b_tree.entry(4) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &4);
+                assert_eq!(entry.insert(40), 4);
+                assert_eq!(entry.remove(), 40);
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+
+        assert_eq!(b_tree.len(), 6);
+        assert!(b_tree.keys().copied().eq([1, 2, 3, 5, 6, 7]));
+    }
+}
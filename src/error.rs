@@ -0,0 +1,53 @@
+use core::fmt;
+
+/// The error returned by [`BinaryTree::try_insert_no_grow`](crate::BinaryTree::try_insert_no_grow)
+/// when the entry does not fit into the current capacity.
+///
+/// Carries the rejected key and value.
+pub struct CapacityError<K, V> {
+    pub key: K,
+    pub value: V,
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for CapacityError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CapacityError")
+            .field("key", &self.key)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Display for CapacityError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no room for key {:?} without growing the tree", self.key)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: fmt::Debug, V: fmt::Debug> std::error::Error for CapacityError<K, V> {}
+
+/// The error returned when converting a `Vec` with a duplicate key into a
+/// [`BinaryTree`](crate::BinaryTree).
+///
+/// Carries one of the duplicated keys.
+pub struct DuplicateKeyError<K> {
+    pub key: K,
+}
+
+impl<K: fmt::Debug> fmt::Debug for DuplicateKeyError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DuplicateKeyError")
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+impl<K: fmt::Debug> fmt::Display for DuplicateKeyError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate key {:?}", self.key)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: fmt::Debug> std::error::Error for DuplicateKeyError<K> {}
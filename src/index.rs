@@ -0,0 +1,44 @@
+/// The integer type a BinaryTree stores its length in and computes the
+/// cell indices of lookups with.
+///
+/// A smaller type shrinks the tree's bookkeeping at the cost of the number
+/// of cells it can address; exceeding that number panics. Implemented for
+/// `u32`, `u64` and `usize`, the default.
+pub trait Index: Copy {
+    /// Converts `n`, panicking if it does not fit.
+    fn from_usize(n: usize) -> Self;
+
+    fn to_usize(self) -> usize;
+
+    /// The index of the left child, or `None` if it does not fit.
+    fn left(self) -> Option<Self>;
+
+    /// The index of the right child, or `None` if it does not fit.
+    fn right(self) -> Option<Self>;
+}
+
+macro_rules! impl_index {
+    ($($ty:ty),*) => {
+        $(
+            impl Index for $ty {
+                fn from_usize(n: usize) -> Self {
+                    <$ty>::try_from(n).expect("value exceeds the index type")
+                }
+
+                fn to_usize(self) -> usize {
+                    usize::try_from(self).expect("length exceeds the address space")
+                }
+
+                fn left(self) -> Option<Self> {
+                    self.checked_mul(2)?.checked_add(1)
+                }
+
+                fn right(self) -> Option<Self> {
+                    self.checked_mul(2)?.checked_add(2)
+                }
+            }
+        )*
+    };
+}
+
+impl_index!(u32, u64, usize);
@@ -1,312 +1,5104 @@
-use std::fmt::Debug;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt::{Debug, Display};
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::ops::{Bound, RangeBounds, Sub};
+
+use builder::Config;
+
+mod builder;
+mod comparator;
+#[cfg(feature = "std")]
+mod concurrent;
+mod cursor;
+mod entry;
+mod error;
+mod index;
+mod macros;
+mod multi;
+#[cfg(feature = "rayon")]
+mod rayon;
+#[cfg(feature = "serde")]
+mod serde;
+mod stats;
+
+pub use builder::Builder;
+pub use comparator::{Comparator, Natural};
+#[cfg(feature = "std")]
+pub use concurrent::ConcurrentBinaryTree;
+pub use cursor::{Cursor, CursorMut};
+pub use entry::{Entry, OccupiedEntry, OccupiedError, VacantEntry};
+pub use error::{CapacityError, DuplicateKeyError};
+pub use index::Index;
+pub use multi::BinaryMultiTree;
+pub use stats::TreeStats;
+
+pub use alloc::collections::TryReserveError;
 
 /// A binary tree implementation based on a slice of Option<(K, V)>
+///
+/// The keys are ordered by the [`Comparator`] `C`, which defaults to their
+/// `Ord` implementation. The length is stored as an [`Index`] `I`.
+///
+/// The nodes do not point at each other, the children of a cell are found by
+/// its index. Dropping the tree therefore just drops the array cell by cell,
+/// using constant stack space however deep the tree is.
 #[derive(Clone)]
-pub struct BinaryTree<K, V>(Box<[Option<(K, V)>]>);
+pub struct BinaryTree<K, V, C = Natural, I = usize> {
+    mem: Box<[Option<(K, V)>]>,
+    len: I,
+    cmp: C,
+    config: Config,
+}
+
+impl<K, V> BinaryTree<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(8)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_comparator(capacity, Natural)
+    }
+
+    /// Creates a tree that never grows beyond `capacity` cells.
+    ///
+    /// Inserting into it never allocates, so it does not rebalance either and
+    /// skewed insert orders run out of cells early. `insert` panics once an
+    /// entry does not fit, use [`try_insert_no_grow`](Self::try_insert_no_grow)
+    /// to handle that case.
+    pub fn with_capacity_fixed(capacity: usize) -> Self {
+        let mut tree = Self::with_capacity(capacity);
+        tree.config.fixed = true;
+        tree
+    }
+
+    /// Configures a tree before creating it.
+    pub fn builder() -> Builder<K, V> {
+        Builder::new()
+    }
+
+    /// Builds a balanced tree from entries sorted by key.
+    ///
+    /// `items` must be sorted and free of duplicate keys.
+    pub fn from_sorted_slice(items: &[(K, V)]) -> Self
+    where
+        K: Ord + Clone,
+        V: Clone,
+    {
+        debug_assert!(
+            items.windows(2).all(|w| w[0].0 < w[1].0),
+            "from_sorted_slice requires sorted, unique keys"
+        );
+
+        let mut tree = Self::new();
+
+        tree.replace_sorted(items.len(), items.iter().cloned());
+
+        tree
+    }
+}
+
+impl<K, V, F> BinaryTree<K, V, F>
+where
+    F: Fn(&K, &K) -> Ordering,
+{
+    /// Creates a tree ordering its keys by `cmp` instead of their `Ord` implementation.
+    pub fn with_comparator(cmp: F) -> Self {
+        Self::with_capacity_and_comparator(8, cmp)
+    }
+}
+
+impl<K, V, C, I: Index> BinaryTree<K, V, C, I> {
+    pub fn with_capacity_and_comparator(mut capacity: usize, cmp: C) -> Self {
+        capacity = capacity.max(1);
+        Self::assert_addressable(capacity);
+
+        Self {
+            mem: core::iter::repeat_with(Default::default)
+                .take(capacity)
+                .collect(),
+            len: I::from_usize(0),
+            cmp,
+            config: Config::default(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.mem.len()
+    }
+
+    /// The number of entries stored in the tree.
+    pub fn len(&self) -> usize {
+        self.len.to_usize()
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.len = I::from_usize(len);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the entries in order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            mem: &self.mem,
+            indexer: BiTreeIndexIter::new(self.capacity()),
+            remaining: self.len(),
+        }
+    }
+
+    /// Consumes the tree into its entries in ascending key order.
+    pub fn into_sorted_vec(self) -> Vec<(K, V)> {
+        self.into_iter().collect()
+    }
+
+    /// Clones the entries into a `Vec` in ascending key order.
+    pub fn to_vec(&self) -> Vec<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+
+    /// Builds a tree of the same shape, with `f` applied to every value.
+    ///
+    /// The new tree reuses the layout of this one, so no keys are compared.
+    pub fn map_values<W, F>(&self, mut f: F) -> BinaryTree<K, W, C, I>
+    where
+        K: Clone,
+        C: Clone,
+        F: FnMut(&V) -> W,
+    {
+        BinaryTree {
+            mem: self
+                .mem
+                .iter()
+                .map(|cell| cell.as_ref().map(|(key, value)| (key.clone(), f(value))))
+                .collect(),
+            len: self.len,
+            cmp: self.cmp.clone(),
+            config: self.config,
+        }
+    }
+
+    /// Iterates over the entries in order, with mutable references to the values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            indexer: BiTreeIndexIter::new(self.capacity()),
+            remaining: self.len(),
+            mem: self.mem.as_mut_ptr(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Iterates over the keys in order.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    /// Iterates over the values in the order of their keys.
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    /// Iterates over mutable references to the values in the order of their keys.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut(self.iter_mut())
+    }
+
+    /// Consumes the tree, yielding the keys in order.
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys(self.into_iter())
+    }
+
+    /// Consumes the tree, yielding the values in the order of their keys.
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues(self.into_iter())
+    }
+
+    /// Returns `true` if any entry holds `value`.
+    ///
+    /// Values are not indexed, so this scans every cell in `O(n)`.
+    pub fn contains_value(&self, value: &V) -> bool
+    where
+        V: PartialEq,
+    {
+        self.mem
+            .iter()
+            .flatten()
+            .any(|(_, r_value)| r_value == value)
+    }
+
+    /// The entry with the smallest value under `cmp`, the first one on ties.
+    pub fn min_by<F: FnMut(&V, &V) -> Ordering>(&self, mut cmp: F) -> Option<(&K, &V)> {
+        self.iter().min_by(|a, b| cmp(a.1, b.1))
+    }
+
+    /// The entry with the largest value under `cmp`, the last one on ties.
+    pub fn max_by<F: FnMut(&V, &V) -> Ordering>(&self, mut cmp: F) -> Option<(&K, &V)> {
+        self.iter().max_by(|a, b| cmp(a.1, b.1))
+    }
+
+    /// Counts the entries for which `f` returns `true`.
+    pub fn count_matching<F: FnMut(&K, &V) -> bool>(&self, mut f: F) -> usize {
+        self.iter().filter(|(key, value)| f(key, value)).count()
+    }
+
+    /// Iterates over the entries in level order, from the root down.
+    pub fn bfs(&self) -> Bfs<'_, K, V> {
+        Bfs {
+            cells: self.mem.iter(),
+            remaining: self.len(),
+        }
+    }
+
+    /// Iterates over the entries root first, then the left and the right subtree.
+    pub fn preorder(&self) -> PreOrder<'_, K, V> {
+        PreOrder {
+            mem: &self.mem,
+            stack: vec![0],
+            remaining: self.len(),
+        }
+    }
+
+    /// Iterates over the entries left subtree first, then the right subtree, then the root.
+    pub fn postorder(&self) -> PostOrder<'_, K, V> {
+        PostOrder {
+            mem: &self.mem,
+            stack: vec![(0, false)],
+            remaining: self.len(),
+        }
+    }
+
+    /// A cursor at the root, to walk the tree by hand.
+    pub fn cursor_at_root(&self) -> Cursor<'_, K, V> {
+        Cursor {
+            mem: &self.mem,
+            index: 0,
+        }
+    }
+
+    /// A mutable cursor at the root, to walk the tree by hand and change
+    /// values on the way.
+    pub fn cursor_mut_at_root(&mut self) -> CursorMut<'_, K, V> {
+        CursorMut {
+            mem: &mut self.mem,
+            index: 0,
+        }
+    }
+
+    /// The entry with the smallest key.
+    ///
+    /// Trees of `Ord` keys and values are `Ord` themselves, and on an owned
+    /// tree `tree.min()` resolves to [`Ord::min`]; use
+    /// [`first_key_value`](Self::first_key_value) there.
+    pub fn min(&self) -> Option<(&K, &V)> {
+        self.first_key_value()
+    }
+
+    /// The entry with the largest key.
+    ///
+    /// On an owned tree of `Ord` keys and values `tree.max()` resolves to
+    /// [`Ord::max`]; use [`last_key_value`](Self::last_key_value) there.
+    pub fn max(&self) -> Option<(&K, &V)> {
+        self.last_key_value()
+    }
+
+    /// Removes and returns the entry with the smallest key.
+    pub fn pop_min(&mut self) -> Option<(K, V)> {
+        let index = self.min_index()?;
+
+        Some(self.remove_at(index))
+    }
+
+    /// Removes and returns the entry with the largest key.
+    pub fn pop_max(&mut self) -> Option<(K, V)> {
+        let index = self.max_index()?;
+
+        Some(self.remove_at(index))
+    }
+
+    /// The entry with the smallest key.
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.entry_at(self.min_index()?)
+    }
+
+    /// The entry with the largest key.
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.entry_at(self.max_index()?)
+    }
+
+    /// Removes and returns the entry with the smallest key. Alias of [`pop_min`](Self::pop_min).
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.pop_min()
+    }
+
+    /// Removes and returns the entry with the largest key. Alias of [`pop_max`](Self::pop_max).
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.pop_max()
+    }
+
+    /// The entry with the smallest key, to change or remove in place.
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, K, V, C, I>> {
+        let index = self.min_index()?;
+
+        Some(OccupiedEntry { tree: self, index })
+    }
+
+    /// The entry with the largest key, to change or remove in place.
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, K, V, C, I>> {
+        let index = self.max_index()?;
+
+        Some(OccupiedEntry { tree: self, index })
+    }
+
+    /// The index of the in-order successor of the occupied cell at `index`.
+    fn next_index(&self, mut index: usize) -> Option<usize> {
+        if let Some(right) = self.occupied_right(index) {
+            index = right;
+
+            while let Some(left) = self.occupied_left(index) {
+                index = left;
+            }
+
+            return Some(index);
+        }
+
+        // climb until we come up from a left child
+        while let Some(parent) = BiTree::parrent(index) {
+            if !BiTree::is_right(index) {
+                return Some(parent);
+            }
+            index = parent;
+        }
+
+        None
+    }
+
+    /// The index of the in-order predecessor of the occupied cell at `index`.
+    fn prev_index(&self, mut index: usize) -> Option<usize> {
+        if let Some(left) = self.occupied_left(index) {
+            index = left;
+
+            while let Some(right) = self.occupied_right(index) {
+                index = right;
+            }
+
+            return Some(index);
+        }
+
+        // climb until we come up from a right child
+        while let Some(parent) = BiTree::parrent(index) {
+            if BiTree::is_right(index) {
+                return Some(parent);
+            }
+            index = parent;
+        }
+
+        None
+    }
+
+    /// The number of levels in the tree, 0 if it is empty.
+    pub fn height(&self) -> usize {
+        self.mem
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |index| BiTree::depth(index) + 1)
+    }
+
+    /// Renders the layout of the tree as a Graphviz `digraph`.
+    ///
+    /// Every occupied cell becomes a node labeled with its key, with edges to
+    /// its children.
+    pub fn to_dot(&self) -> String
+    where
+        K: Debug,
+    {
+        let mut dot = String::from("digraph {\n");
+
+        for (index, cell) in self.mem.iter().enumerate() {
+            let Some((key, _)) = cell else { continue };
+
+            let label = format!("{:?}", key)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            dot += &format!("    n{} [label=\"{}\"];\n", index, label);
+
+            for child in [self.occupied_left(index), self.occupied_right(index)]
+                .into_iter()
+                .flatten()
+            {
+                dot += &format!("    n{} -> n{};\n", index, child);
+            }
+        }
+
+        dot += "}\n";
+        dot
+    }
+
+    /// The number of entries without children.
+    pub fn count_leaves(&self) -> usize {
+        self.len() - self.count_internal()
+    }
+
+    /// The number of entries with at least one child.
+    pub fn count_internal(&self) -> usize {
+        (0..self.capacity())
+            .filter(|&index| {
+                self.is_occupied(index)
+                    && (self.occupied_left(index).is_some() || self.occupied_right(index).is_some())
+            })
+            .count()
+    }
+
+    /// Whether every entry can be reached by descending from the root, that
+    /// is whether no entry sits below an empty cell.
+    pub fn validate_reachable(&self) -> bool {
+        self.first_unreachable().is_none()
+    }
+
+    /// The smallest index of an entry below an empty cell.
+    fn first_unreachable(&self) -> Option<usize> {
+        (1..self.capacity()).find(|&index| {
+            self.is_occupied(index)
+                && !BiTree::parrent(index).is_some_and(|parent| self.is_occupied(parent))
+        })
+    }
+
+    /// Whether every level is full except possibly the last, which is filled
+    /// from the left.
+    ///
+    /// In the array layout this means the entries occupy exactly the first
+    /// `len()` cells.
+    pub fn is_complete(&self) -> bool {
+        self.mem.iter().take_while(|cell| cell.is_some()).count() == self.len()
+    }
+
+    /// Whether every level is completely full.
+    pub fn is_perfect(&self) -> bool {
+        self.is_complete() && (self.len() + 1).is_power_of_two()
+    }
+
+    /// Whether the subtree heights of every node differ by at most one.
+    pub fn is_balanced(&self) -> bool {
+        self.balanced_subtree_height(Some(0)).is_some()
+    }
+
+    /// The height of the subtree rooted at `index` if it is balanced.
+    fn balanced_subtree_height(&self, index: Option<usize>) -> Option<usize> {
+        let Some(index) = index.filter(|&index| self.is_occupied(index)) else {
+            return Some(0);
+        };
+
+        let left = self.balanced_subtree_height(BiTree::left(index))?;
+        let right = self.balanced_subtree_height(BiTree::right(index))?;
+
+        (left.abs_diff(right) <= 1).then(|| left.max(right) + 1)
+    }
+
+    /// The `k`-th smallest entry, counting from zero.
+    ///
+    /// No subtree sizes are stored, so this walks the entries in order. Empty
+    /// subtrees are skipped rather than visited cell by cell like `iter` does,
+    /// which makes it `O(k + height)`.
+    pub fn nth(&self, mut k: usize) -> Option<(&K, &V)> {
+        let mut stack = Vec::new();
+        let mut current = Some(0);
+
+        loop {
+            while let Some(node) = current.filter(|&node| self.is_occupied(node)) {
+                stack.push(node);
+                current = BiTree::left(node);
+            }
+
+            let node = stack.pop()?;
+
+            if k == 0 {
+                return self.entry_at(node);
+            }
+
+            k -= 1;
+            current = BiTree::right(node);
+        }
+    }
+
+    /// Rebuilds the tree into a balanced layout, using the smallest power of
+    /// two capacity that fits.
+    ///
+    /// A fixed capacity is kept instead. Panics if it is too small for a
+    /// balanced layout, which can happen when it is not a power of two.
+    pub fn rebalance(&mut self) {
+        self.rebuild(self.rebuild_capacity(self.len()));
+    }
+
+    /// Shrinks the capacity to the smallest balanced array holding all entries.
+    ///
+    /// Unlike `rebalance`, this does nothing if the capacity is already minimal.
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the capacity to the smallest balanced array holding all entries,
+    /// but no further than `min_capacity`.
+    ///
+    /// Does nothing if the capacity is already at most that or fixed.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let capacity = Self::balanced_capacity(self.len()).max(min_capacity);
+
+        if !self.config.fixed && self.capacity() > capacity {
+            self.rebuild(capacity);
+        }
+    }
+
+    /// Grows the capacity so that `additional` more entries can be inserted
+    /// without growing again.
+    ///
+    /// Inserts keep every new entry within one level of a balanced tree, so
+    /// this reserves the levels of a balanced tree of `len() + additional`
+    /// entries plus one spare level.
+    ///
+    /// Does nothing if the capacity is fixed.
+    pub fn reserve(&mut self, additional: usize) {
+        if self.config.fixed {
+            return;
+        }
+
+        let levels = Self::balanced_height(self.len() + additional) + 1;
+
+        self.grow_to(1 << levels);
+    }
+
+    /// Like [`reserve`](Self::reserve), but returns an error instead of
+    /// aborting if the allocation fails or the capacity would overflow.
+    ///
+    /// Does nothing if the capacity is fixed.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        if self.config.fixed {
+            return Ok(());
+        }
+
+        let levels = self
+            .len()
+            .checked_add(additional)
+            .map_or(usize::BITS as usize, |len| Self::balanced_height(len) + 1);
+
+        // An overflowing capacity is left for the allocation to reject.
+        let new_capacity = u32::try_from(levels)
+            .ok()
+            .and_then(|levels| 1usize.checked_shl(levels))
+            .unwrap_or(usize::MAX);
+
+        if new_capacity <= self.capacity() {
+            return Ok(());
+        }
+
+        Self::assert_addressable(new_capacity);
+
+        let mut mem = Vec::new();
+        mem.try_reserve_exact(new_capacity)?;
+
+        mem.extend(self.mem.iter_mut().map(Option::take));
+        mem.resize_with(new_capacity, Default::default);
+
+        self.mem = mem.into_boxed_slice();
+
+        Ok(())
+    }
+
+    /// Panics if `I` cannot address every cell of an array of `capacity`,
+    /// as lookups compute their indices in it.
+    fn assert_addressable(capacity: usize) {
+        I::from_usize(capacity.saturating_sub(1));
+    }
+
+    /// Moves the entries into a larger array, keeping their indices.
+    fn grow_to(&mut self, new_capacity: usize) {
+        if new_capacity <= self.capacity() {
+            return;
+        }
+
+        Self::assert_addressable(new_capacity);
+
+        self.mem = self
+            .mem
+            .iter_mut() // We have to do iter_mut to move everything
+            .map(Option::take) // We move out of old_inner
+            .chain(core::iter::repeat_with(Default::default))
+            .take(new_capacity)
+            .collect();
+    }
+
+    /// Keeps only the entries for which `f` returns `true`.
+    ///
+    /// The removed entries are taken out in place and the survivors rebuilt
+    /// balanced, so it is `O(n)` even if nothing is removed. The capacity is
+    /// kept, and only grows if the survivors do not fit a balanced layout. If
+    /// it is fixed and too small for that, the survivors stay where they are.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        let doomed = Self::matching_indices(&mut self.mem, |key, value| !f(key, value));
+
+        self.remove_indices(doomed);
+        self.rebalance_kept();
+    }
+
+    /// Splits the tree into the entries for which `f` returns `true` and the rest.
+    ///
+    /// Both halves are rebuilt balanced. A fixed capacity is kept for both,
+    /// and the call panics if a half does not fit it balanced.
+    pub fn partition<F: FnMut(&K, &V) -> bool>(mut self, mut f: F) -> (Self, Self)
+    where
+        C: Clone,
+    {
+        let (matching, rest): (Vec<_>, Vec<_>) = self
+            .take_subtree(0)
+            .into_iter()
+            .partition(|(key, value)| f(key, value));
+
+        let mut other = self.empty_like();
+
+        other.replace_sorted(rest.len(), rest);
+        self.replace_sorted(matching.len(), matching);
+
+        (self, other)
+    }
+
+    /// Removes all entries, yielding them in order and keeping the capacity.
+    ///
+    /// The entries are moved out of the tree right away, so it is empty even
+    /// if the `Drain` is leaked. Entries not yet yielded when the `Drain` is
+    /// dropped are dropped too.
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        let entries = self.take_subtree(0);
+        self.set_len(0);
+
+        Drain {
+            entries: entries.into_iter(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Yields and removes the entries for which `pred` returns `true`, in order.
+    ///
+    /// The entries not extracted, including those not yet visited when the
+    /// iterator is dropped, are put back and rebuilt balanced on drop like
+    /// [`retain`](Self::retain) does. If the iterator is leaked, the tree is
+    /// left empty.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F, C, I>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let entries: Vec<_> = BiTreeIndexIter::new(self.capacity())
+            .filter_map(|index| Some((index, self.mem[index].take()?)))
+            .collect();
+        self.set_len(0);
+
+        ExtractIf {
+            kept: Vec::with_capacity(entries.len()),
+            extracted: Vec::new(),
+            entries: entries.into_iter(),
+            tree: self,
+            pred,
+        }
+    }
+
+    /// Removes all entries, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.mem.iter_mut().for_each(|cell| *cell = None);
+        self.set_len(0);
+    }
+
+    /// An empty tree with the comparator and configuration of this one, and
+    /// its capacity if that is fixed.
+    fn empty_like(&self) -> Self
+    where
+        C: Clone,
+    {
+        let capacity = if self.config.fixed {
+            self.capacity()
+        } else {
+            1
+        };
+
+        let mut tree = Self::with_capacity_and_comparator(capacity, self.cmp.clone());
+        tree.config = self.config;
+        tree
+    }
+
+    /// The capacity to rebuild `len` entries into: the smallest balanced one,
+    /// or the current one if it is fixed.
+    fn rebuild_capacity(&self, len: usize) -> usize {
+        if self.config.fixed {
+            self.assert_fits_balanced(len);
+            self.capacity()
+        } else {
+            Self::balanced_capacity(len)
+        }
+    }
+
+    /// Rebuilds the entries balanced, keeping the capacity, which only grows
+    /// if it is too small and not fixed. A fixed capacity too small for a
+    /// balanced layout leaves the entries where they are.
+    fn rebalance_kept(&mut self) {
+        if !self.config.fixed {
+            self.rebuild(self.capacity().max(Self::balanced_capacity(self.len())));
+        } else if self.fits_balanced(self.len()) {
+            self.rebuild(self.capacity());
+        }
+    }
+
+    /// The indices of the entries for which `f` returns `true`, calling it on
+    /// the entries in order.
+    fn matching_indices<F>(mem: &mut [Option<(K, V)>], mut f: F) -> Vec<usize>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        BiTreeIndexIter::new(mem.len())
+            .filter(|&index| match &mut mem[index] {
+                Some((key, value)) => f(key, value),
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Drops the entries at `indices` and fills their cells from below.
+    fn remove_indices(&mut self, indices: Vec<usize>) {
+        for &index in &indices {
+            self.mem[index] = None;
+        }
+
+        self.set_len(self.len() - indices.len());
+        self.fill_holes(indices);
+    }
+
+    /// Fills the empty cells at `holes` like [`remove_at`](Self::remove_at).
+    ///
+    /// Filling a hole only moves entries within its subtree, whose indices
+    /// are all larger, so going from the largest index down each hole sees an
+    /// intact subtree below it and leaves the smaller ones where they are.
+    fn fill_holes(&mut self, mut holes: Vec<usize>) {
+        holes.sort_unstable_by(|a, b| b.cmp(a));
+
+        for hole in holes {
+            self.fill_hole(hole);
+        }
+    }
+
+    /// Panics if the capacity is fixed and a balanced layout of `len` entries
+    /// does not fit into it.
+    fn assert_fits_balanced(&self, len: usize) {
+        assert!(
+            !self.config.fixed || self.fits_balanced(len),
+            "the fixed capacity of the tree is exhausted"
+        );
+    }
+
+    /// Whether `fill_balanced(0, len, ..)` stays within the current capacity.
+    fn fits_balanced(&self, len: usize) -> bool {
+        let mut index = 0;
+        let mut count = len;
+
+        while count > 1 {
+            let left = count / 2;
+            let right = count - 1 - left;
+
+            // The last index is on the deepest level, in the right subtree if
+            // both reach down to it.
+            let next = if Self::balanced_height(right) == Self::balanced_height(left) {
+                count = right;
+                BiTree::right(index)
+            } else {
+                count = left;
+                BiTree::left(index)
+            };
+
+            match next {
+                Some(next) => index = next,
+                None => return false,
+            }
+        }
+
+        len == 0 || index < self.capacity()
+    }
+
+    /// The smallest capacity able to hold `len` entries in a balanced layout.
+    fn balanced_capacity(len: usize) -> usize {
+        ((1usize << Self::balanced_height(len)) - 1)
+            .max(1)
+            .next_power_of_two()
+    }
+
+    /// The number of levels of a balanced tree holding `len` entries.
+    fn balanced_height(len: usize) -> usize {
+        (usize::BITS - len.leading_zeros()) as usize
+    }
+
+    /// The number of entries in the subtree rooted at `index`.
+    fn subtree_len(&self, index: usize) -> usize {
+        let mut count = 0;
+        let mut stack = vec![index];
+
+        while let Some(node) = stack.pop() {
+            if self.is_occupied(node) {
+                count += 1;
+                stack.extend(BiTree::left(node));
+                stack.extend(BiTree::right(node));
+            }
+        }
+
+        count
+    }
+
+    /// Moves the entries of the subtree rooted at `index` out in order.
+    ///
+    /// The caller is responsible for `len`.
+    fn take_subtree(&mut self, index: usize) -> Vec<(K, V)> {
+        let mut entries = Vec::new();
+        let mut stack = Vec::new();
+        let mut current = Some(index);
+
+        loop {
+            while let Some(node) = current.filter(|&node| self.is_occupied(node)) {
+                stack.push(node);
+                current = BiTree::left(node);
+            }
+
+            if let Some(node) = stack.pop() {
+                entries.extend(self.mem[node].take());
+                current = BiTree::right(node);
+            } else {
+                return entries;
+            }
+        }
+    }
+
+    /// Replaces all entries with `len` entries yielded in order by `items`,
+    /// laid out balanced in the smallest fitting capacity, or in the current
+    /// one if it is fixed.
+    ///
+    /// Panics if the fixed capacity is too small. Callers that keep the tree
+    /// check that before taking the entries out.
+    fn replace_sorted<T>(&mut self, len: usize, items: T)
+    where
+        T: IntoIterator<Item = (K, V)>,
+    {
+        self.refill(self.rebuild_capacity(len), len, items);
+    }
+
+    /// Replaces all entries with `len` entries yielded in order by `items`,
+    /// laid out balanced in a fresh array of `capacity` cells.
+    ///
+    /// `capacity` must fit a balanced layout of `len` entries.
+    fn refill<T>(&mut self, capacity: usize, len: usize, items: T)
+    where
+        T: IntoIterator<Item = (K, V)>,
+    {
+        Self::assert_addressable(capacity);
+
+        self.mem = core::iter::repeat_with(Default::default)
+            .take(capacity)
+            .collect();
+
+        self.fill_balanced(0, len, &mut items.into_iter());
+        self.set_len(len);
+    }
+
+    /// The index `fill_balanced(index, count, ..)` writes the entry at
+    /// `position` of the stream to.
+    fn balanced_index(mut index: usize, mut count: usize, mut position: usize) -> usize {
+        loop {
+            let left = count / 2;
+
+            match position.cmp(&left) {
+                Ordering::Less => {
+                    index = BiTree::left(index).expect(BALANCED_FITS);
+                    count = left;
+                }
+                Ordering::Equal => return index,
+                Ordering::Greater => {
+                    index = BiTree::right(index).expect(BALANCED_FITS);
+                    position -= left + 1;
+                    count -= left + 1;
+                }
+            }
+        }
+    }
+
+    /// Moves all entries into a fresh balanced array of `capacity` cells.
+    ///
+    /// `capacity` must fit a balanced layout of `len` entries.
+    fn rebuild(&mut self, capacity: usize) {
+        let entries = self.take_subtree(0);
+
+        self.refill(capacity, entries.len(), entries);
+    }
+
+    /// Writes `count` entries from the in-order stream `items` into the subtree
+    /// rooted at `index`, always picking the median as the root.
+    ///
+    /// The caller is responsible for the capacity and `len`.
+    fn fill_balanced<T>(&mut self, index: usize, count: usize, items: &mut T)
+    where
+        T: Iterator<Item = (K, V)>,
+    {
+        if count == 0 {
+            return;
+        }
+
+        let left = count / 2;
+
+        self.fill_balanced(BiTree::left(index).expect(BALANCED_FITS), left, items);
+        self.mem[index] = items.next();
+        self.fill_balanced(
+            BiTree::right(index).expect(BALANCED_FITS),
+            count - left - 1,
+            items,
+        );
+    }
+
+    fn is_occupied(&self, index: usize) -> bool {
+        matches!(self.mem.get(index), Some(Some(_)))
+    }
+
+    /// The left child of `index`, if it holds an entry.
+    fn occupied_left(&self, index: usize) -> Option<usize> {
+        BiTree::left(index).filter(|&child| self.is_occupied(child))
+    }
+
+    /// The right child of `index`, if it holds an entry.
+    fn occupied_right(&self, index: usize) -> Option<usize> {
+        BiTree::right(index).filter(|&child| self.is_occupied(child))
+    }
+
+    fn entry_at(&self, index: usize) -> Option<(&K, &V)> {
+        self.mem
+            .get(index)
+            .and_then(|cell| cell.as_ref())
+            .map(|(key, value)| (key, value))
+    }
+
+    /// Walks left from the root as far as possible.
+    fn min_index(&self) -> Option<usize> {
+        if !self.is_occupied(0) {
+            return None;
+        }
+
+        let mut index = 0;
+
+        while let Some(left) = self.occupied_left(index) {
+            index = left;
+        }
+
+        Some(index)
+    }
+
+    /// Walks right from the root as far as possible.
+    fn max_index(&self) -> Option<usize> {
+        if !self.is_occupied(0) {
+            return None;
+        }
+
+        let mut index = 0;
+
+        while let Some(right) = self.occupied_right(index) {
+            index = right;
+        }
+
+        Some(index)
+    }
+
+    /// Takes the entry at `index` out of the tree.
+    fn remove_at(&mut self, index: usize) -> (K, V) {
+        let removed = self.mem[index].take().expect("remove_at on an empty cell");
+        self.set_len(self.len() - 1);
+        self.fill_hole(index);
+
+        removed
+    }
+
+    /// Fills the empty cell at `index` from its subtree.
+    ///
+    /// The hole is filled with the in-order successor (or, lacking a right
+    /// subtree, the predecessor), whose own slot is then filled the same way
+    /// until a leaf is vacated. This keeps every remaining entry reachable
+    /// from the root without having to shift whole subtrees.
+    fn fill_hole(&mut self, mut index: usize) {
+        loop {
+            let replacement = if let Some(mut node) = self.occupied_right(index) {
+                // leftmost node of the right subtree
+                while let Some(left) = self.occupied_left(node) {
+                    node = left;
+                }
+                node
+            } else if let Some(mut node) = self.occupied_left(index) {
+                // rightmost node of the left subtree
+                while let Some(right) = self.occupied_right(node) {
+                    node = right;
+                }
+                node
+            } else {
+                // index is a leaf now
+                break;
+            };
+
+            self.mem[index] = self.mem[replacement].take();
+            index = replacement;
+        }
+    }
+}
+
+impl<K, V, I: Index> Default for BinaryTree<K, V, Natural, I> {
+    fn default() -> Self {
+        Self::with_capacity_and_comparator(8, Natural)
+    }
+}
+
+/// Trees are equal when they hold the same entries, regardless of their layout.
+impl<K: PartialEq, V: PartialEq, C, I: Index> PartialEq for BinaryTree<K, V, C, I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K: Eq, V: Eq, C, I: Index> Eq for BinaryTree<K, V, C, I> {}
+
+/// Compares the entries in order lexicographically, consistent with `PartialEq`.
+impl<K: PartialOrd, V: PartialOrd, C, I: Index> PartialOrd for BinaryTree<K, V, C, I> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+/// Compares the entries in order lexicographically, consistent with `Eq`.
+///
+/// This brings [`Ord::min`] and [`Ord::max`], which take `self` by value and
+/// so win over [`BinaryTree::min`] and [`BinaryTree::max`] on owned trees;
+/// [`first_key_value`](BinaryTree::first_key_value) and
+/// [`last_key_value`](BinaryTree::last_key_value) are unambiguous.
+impl<K: Ord, V: Ord, C, I: Index> Ord for BinaryTree<K, V, C, I> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+/// Hashes the entries in order, consistent with `PartialEq`.
+impl<K: Hash, V: Hash, C, I: Index> Hash for BinaryTree<K, V, C, I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+
+        for entry in self.iter() {
+            entry.hash(state);
+        }
+    }
+}
+
+/// Formats the entries as a map in key order.
+///
+/// The alternate form shows the layout instead, mapping the index of every
+/// occupied cell to its entry.
+impl<K: Debug, V: Debug, C, I: Index> Debug for BinaryTree<K, V, C, I> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if f.alternate() {
+            f.debug_map()
+                .entries(
+                    self.mem
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(index, cell)| Some((index, cell.as_ref()?))),
+                )
+                .finish()
+        } else {
+            f.debug_map().entries(self.iter()).finish()
+        }
+    }
+}
+
+/// Draws the tree sideways with the root on the first line and every child
+/// indented below its parent, the left one first.
+///
+/// The alternate form also marks empty child cells of inner nodes, so a lone
+/// child can be told apart as left or right.
+impl<K: Display, V, C, I: Index> Display for BinaryTree<K, V, C, I> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let Some((key, _)) = self.entry_at(0) else {
+            return Ok(());
+        };
+
+        writeln!(f, "{}", key)?;
+
+        self.fmt_children(f, 0, &mut String::new())
+    }
+}
+
+impl<K: Display, V, C, I: Index> BinaryTree<K, V, C, I> {
+    /// Writes the subtrees below the occupied cell at `index`, starting every
+    /// line with `prefix`.
+    fn fmt_children(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+        index: usize,
+        prefix: &mut String,
+    ) -> core::fmt::Result {
+        let children = [BiTree::left(index), BiTree::right(index)];
+        let any_child = children
+            .iter()
+            .flatten()
+            .any(|&child| self.is_occupied(child));
+
+        let shown: Vec<_> = children
+            .into_iter()
+            .flatten()
+            .filter(|&child| self.is_occupied(child) || (f.alternate() && any_child))
+            .collect();
+
+        for (n, &child) in shown.iter().enumerate() {
+            let last = n + 1 == shown.len();
+            let branch = if last { "└──" } else { "├──" };
+
+            let Some((key, _)) = self.entry_at(child) else {
+                writeln!(f, "{}{}", prefix, branch)?;
+                continue;
+            };
+
+            writeln!(f, "{}{} {}", prefix, branch, key)?;
+
+            let len = prefix.len();
+            prefix.push_str(if last { "    " } else { "│   " });
+            self.fmt_children(f, child, prefix)?;
+            prefix.truncate(len);
+        }
+
+        Ok(())
+    }
+}
+
+/// ```text
+///                 E0
+///         +-------+-------+
+///         E1              E2
+///     +---+---+       +---+---+
+///     E3      ()      E5      E6
+///   +-+-+   +-+-+   +-+-+   +-+-+
+///   ()  E8  ()  ()  E11 E12 ()  E14
+///
+/// +----+----+----+----+----+----+----+----+----+----+----+-----+-----+----+-----+
+/// | E0 | E1 | E2 | E3 | () | E5 | E6 | () | E8 | () | () | E11 | E12 | () | E14 |
+/// +----+----+----+----+----+----+----+----+----+----+----+-----+-----+----+-----+
+/// ```
+impl<K, V, C, I> BinaryTree<K, V, C, I>
+where
+    C: Comparator<K>,
+    I: Index,
+{
+    /// Moves all entries with keys greater than or equal to `key` into a new tree.
+    ///
+    /// Both halves are rebuilt balanced. A fixed capacity is kept for both,
+    /// and the call panics without changing the tree if a half does not fit
+    /// it balanced.
+    pub fn split_off(&mut self, key: &K) -> Self
+    where
+        C: Clone,
+    {
+        if self.config.fixed {
+            let lower = self.rank(key);
+
+            self.assert_fits_balanced(lower);
+            self.assert_fits_balanced(self.len() - lower);
+        }
+
+        let (left, right): (Vec<_>, Vec<_>) = self
+            .take_subtree(0)
+            .into_iter()
+            .partition(|(r_key, _)| self.cmp.compare(r_key, key) == Ordering::Less);
+
+        let mut upper = self.empty_like();
+
+        upper.replace_sorted(right.len(), right);
+        self.replace_sorted(left.len(), left);
+
+        upper
+    }
+
+    /// Moves all entries of `other` into this tree, leaving `other` empty.
+    ///
+    /// On equal keys the entry of `other` wins. Both trees are merged as
+    /// sorted streams into a freshly balanced tree. A fixed capacity is kept,
+    /// and the call panics without changing either tree if the merged entries
+    /// do not fit it balanced.
+    pub fn append(&mut self, other: &mut Self) {
+        if self.config.fixed {
+            let shared = other.keys().filter(|key| self.contains_key(*key)).count();
+
+            self.assert_fits_balanced(self.len() + other.len() - shared);
+        }
+
+        let ours = self.take_subtree(0);
+        let theirs = other.take_subtree(0);
+        other.set_len(0);
+
+        let merged = self.merge_sorted(ours, theirs);
+
+        self.replace_sorted(merged.len(), merged);
+    }
+
+    /// Inserts a batch of entries sorted by key in one pass.
+    ///
+    /// The batch is merged with the entries of the tree as sorted streams
+    /// into a freshly balanced tree, instead of inserting one by one. On
+    /// equal keys the entry of the batch wins. `sorted` must be sorted and
+    /// free of duplicate keys, which is checked in debug builds.
+    ///
+    /// A fixed capacity is kept, and the call panics without changing the
+    /// tree if the merged entries do not fit it balanced.
+    pub fn bulk_insert_sorted(&mut self, sorted: impl IntoIterator<Item = (K, V)>) {
+        let theirs: Vec<_> = sorted.into_iter().collect();
+
+        debug_assert!(
+            theirs
+                .windows(2)
+                .all(|w| self.cmp.compare(&w[0].0, &w[1].0) == Ordering::Less),
+            "bulk_insert_sorted requires sorted, unique keys"
+        );
+
+        if self.config.fixed {
+            let shared = theirs
+                .iter()
+                .filter(|(key, _)| self.contains_key(key))
+                .count();
+
+            self.assert_fits_balanced(self.len() + theirs.len() - shared);
+        }
+
+        let ours = self.take_subtree(0);
+        let merged = self.merge_sorted(ours, theirs);
+
+        self.replace_sorted(merged.len(), merged);
+    }
+
+    /// Merges two in-order streams, preferring `theirs` on equal keys.
+    fn merge_sorted<A, B>(&self, ours: A, theirs: B) -> Vec<(K, V)>
+    where
+        A: IntoIterator<Item = (K, V)>,
+        B: IntoIterator<Item = (K, V)>,
+    {
+        let mut ours = ours.into_iter().peekable();
+        let mut theirs = theirs.into_iter().peekable();
+
+        let mut merged = Vec::with_capacity(ours.size_hint().0 + theirs.size_hint().0);
+
+        loop {
+            let next = match (ours.peek(), theirs.peek()) {
+                (Some(a), Some(b)) => match self.cmp.compare(&a.0, &b.0) {
+                    Ordering::Less => ours.next(),
+                    Ordering::Greater => theirs.next(),
+                    Ordering::Equal => {
+                        ours.next();
+                        theirs.next()
+                    }
+                },
+                (Some(_), None) => ours.next(),
+                (None, _) => theirs.next(),
+            };
+
+            match next {
+                Some(kv) => merged.push(kv),
+                None => break,
+            }
+        }
+
+        merged
+    }
+
+    /// Yields the keys present in both trees, in ascending order.
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a K> + 'a {
+        self.merge_keys(other)
+            .filter_map(|(ours, theirs)| theirs.and(ours))
+    }
+
+    /// Yields the keys present in either tree, in ascending order.
+    ///
+    /// Keys present in both are yielded once, from `self`.
+    pub fn union<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a K> + 'a {
+        self.merge_keys(other)
+            .filter_map(|(ours, theirs)| ours.or(theirs))
+    }
+
+    /// Yields the keys present in `self` but not in `other`, in ascending order.
+    pub fn difference<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a K> + 'a {
+        self.merge_keys(other)
+            .filter_map(|(ours, theirs)| if theirs.is_none() { ours } else { None })
+    }
+
+    /// Returns `true` if every key of `self` is also in `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.merge_keys(other)
+            .all(|(ours, theirs)| ours.is_none() || theirs.is_some())
+    }
+
+    /// Returns `true` if every key of `other` is also in `self`.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns `true` if the trees have no key in common.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    /// Merges the in-order key streams of both trees, pairing up equal keys.
+    fn merge_keys<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> impl Iterator<Item = (Option<&'a K>, Option<&'a K>)> + 'a {
+        let mut ours = self.keys().peekable();
+        let mut theirs = other.keys().peekable();
+
+        core::iter::from_fn(move || match (ours.peek(), theirs.peek()) {
+            (Some(a), Some(b)) => match self.cmp.compare(a, b) {
+                Ordering::Less => Some((ours.next(), None)),
+                Ordering::Greater => Some((None, theirs.next())),
+                Ordering::Equal => Some((ours.next(), theirs.next())),
+            },
+            (Some(_), None) => Some((ours.next(), None)),
+            (None, Some(_)) => Some((None, theirs.next())),
+            (None, None) => None,
+        })
+    }
+
+    /// Locates the cell of `key`, or the vacant cell it belongs in.
+    ///
+    /// `key` may be any borrowed form of `K` the comparator can order.
+    fn find_index<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let Self { mem, .. } = self;
+
+        // The descent runs in `I`, which addresses every cell.
+        let mut index = I::from_usize(0);
+
+        while let Some(
+            // the location exists
+            Some(
+                // and there is something
+                (r_key, _),
+            ),
+        ) = mem.get(index.to_usize())
+        {
+            // Walk further
+            let child = match self.cmp.compare(key, r_key.borrow()) {
+                // Found Entry
+                Ordering::Equal => break,
+                Ordering::Less => BiTree::left(index),
+                Ordering::Greater => BiTree::right(index),
+            };
+
+            match child {
+                Some(child) => index = child,
+                None => return NO_CELL,
+            }
+        }
+
+        index.to_usize()
+    }
+
+    /// Like `find_index`, but without data-dependent branches: the descent
+    /// runs once per level of the capacity, always picks a child
+    /// arithmetically, and only the cell it ends up at is checked for `key`.
+    ///
+    /// Counting from 1, the path is a bit string with a 1 for every step to
+    /// the right. Empty cells and cells past the array step right as well, so
+    /// stripping the trailing ones and the zero before them leaves the last
+    /// cell the descent went left at, which holds the smallest key not less
+    /// than `key` along the path. The four grandchildren of a cell are
+    /// adjacent from `4 * index + 3` on, and both ends of them are prefetched
+    /// since they span two cache lines for larger entries.
+    fn find_index_branchless<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let Self { mem, .. } = self;
+
+        let levels = usize::BITS - mem.len().leading_zeros();
+        let mut position = 1usize;
+
+        for _ in 0..levels {
+            let index = position - 1;
+
+            let grandchildren = index.wrapping_mul(4);
+
+            prefetch(mem.as_ptr().wrapping_add(grandchildren.wrapping_add(3)));
+            prefetch(mem.as_ptr().wrapping_add(grandchildren.wrapping_add(6)));
+
+            let right = match mem.get(index) {
+                Some(Some((r_key, _))) => {
+                    self.cmp.compare(key, r_key.borrow()) == Ordering::Greater
+                }
+                _ => true,
+            };
+
+            position = 2 * position + usize::from(right);
+        }
+
+        position = position
+            .checked_shr(position.trailing_ones() + 1)
+            .unwrap_or(0);
+
+        match position
+            .checked_sub(1)
+            .and_then(|index| Some((index, mem.get(index)?.as_ref()?)))
+        {
+            Some((index, (r_key, _)))
+                if self.cmp.compare(key, r_key.borrow()) == Ordering::Equal =>
+            {
+                index
+            }
+            _ => NO_CELL,
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let index = self.find_index(&key);
+
+        if let Some(cell @ Some(_)) = self.mem.get_mut(index) {
+            cell.replace((key, value)).map(|kv| kv.1)
+        } else {
+            self.insert_vacant(index, key, value);
+
+            None
+        }
+    }
+
+    /// Inserts like [`insert`](Self::insert), but never grows the capacity.
+    ///
+    /// Hands the pair back if there is no room for it.
+    pub fn try_insert_no_grow(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Result<Option<V>, CapacityError<K, V>> {
+        let index = self.find_index(&key);
+
+        if let Some(cell @ Some(_)) = self.mem.get_mut(index) {
+            return Ok(cell.replace((key, value)).map(|kv| kv.1));
+        }
+
+        self.try_insert_vacant(index, key, value, false)
+            .map(|_| None)
+            .map_err(|(key, value)| CapacityError { key, value })
+    }
+
+    /// Gets the entry for `key` for in-place manipulation.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, C, I> {
+        let index = self.find_index(&key);
+
+        if self.is_occupied(index) {
+            Entry::Occupied(OccupiedEntry { tree: self, index })
+        } else {
+            Entry::Vacant(VacantEntry {
+                tree: self,
+                key,
+                index,
+            })
+        }
+    }
+
+    /// Inserts `value` only if `key` is absent and returns a reference to it.
+    ///
+    /// If the key is present, the tree is left unchanged and the error hands
+    /// back the rejected pair together with the existing entry.
+    pub fn try_insert(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Result<&mut V, OccupiedError<'_, K, V, C, I>> {
+        let index = self.find_index(&key);
+
+        if self.is_occupied(index) {
+            Err(OccupiedError {
+                entry: OccupiedEntry { tree: self, index },
+                key,
+                value,
+            })
+        } else {
+            Ok(VacantEntry {
+                tree: self,
+                key,
+                index,
+            }
+            .insert(value))
+        }
+    }
+
+    /// Returns the value for `key`, inserting the result of `default` first if
+    /// the key is absent. `default` is only called in that case.
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, default: F) -> &mut V {
+        self.entry(key).or_insert_with(default)
+    }
+
+    /// Inserts `value`, or if `key` is present replaces its value with the
+    /// result of `f` applied to the old and the new value.
+    pub fn merge_with<F: FnMut(&V, V) -> V>(&mut self, key: K, value: V, mut f: F) {
+        let index = self.find_index(&key);
+
+        if let Some(Some((_, old))) = self.mem.get_mut(index) {
+            let merged = f(old, value);
+            *old = merged;
+        } else {
+            self.insert_vacant(index, key, value);
+        }
+    }
+
+    /// Stores a new entry at the vacant `index` returned by `find_index` and
+    /// returns the index it ended up at.
+    ///
+    /// Panics if the capacity is fixed and there is no room for the entry.
+    fn insert_vacant(&mut self, index: usize, key: K, value: V) -> usize {
+        let grow = !self.config.fixed;
+
+        self.try_insert_vacant(index, key, value, grow)
+            .unwrap_or_else(|_| panic!("the fixed capacity of the tree is exhausted"))
+    }
+
+    /// Like `insert_vacant`, but hands the entry back if it does not fit
+    /// without growing and `grow` is `false`.
+    ///
+    /// A tree with fixed capacity stores every entry where `find_index` put
+    /// it, as rebalancing would allocate.
+    fn try_insert_vacant(
+        &mut self,
+        mut index: usize,
+        key: K,
+        value: V,
+        grow: bool,
+    ) -> Result<usize, (K, V)> {
+        loop {
+            if !self.config.fixed && BiTree::depth(index) > Self::balanced_height(self.len() + 1) {
+                // The new entry would sit more than one level below a balanced
+                // tree of the new size, so we rebuild the tree around it instead.
+                let (root, count) = self.rebuild_root(index);
+
+                if grow || Self::last_index(root, count) < self.capacity() {
+                    return Ok(self.insert_rebuilding(root, count, (key, value)));
+                }
+            }
+
+            if let Some(cell) = self.mem.get_mut(index) {
+                *cell = Some((key, value));
+                self.set_len(self.len() + 1);
+
+                return Ok(index);
+            }
+
+            if !grow {
+                return Err((key, value));
+            }
+
+            self.grow();
+
+            // growing may have moved the entries
+            index = self.find_index(&key);
+        }
+    }
+
+    /// Finds the lowest sparse enough subtree above the vacant `index` to
+    /// take one more entry, returning its root and its number of entries
+    /// including the new one.
+    fn rebuild_root(&self, index: usize) -> (usize, usize) {
+        let max_depth = Self::balanced_height(self.len() + 1);
+
+        let mut root = index;
+        let mut count = 1;
+
+        while let Some(parent) = BiTree::parrent(root) {
+            let sibling = if BiTree::is_right(root) {
+                BiTree::left(parent)
+            } else {
+                BiTree::right(parent)
+            };
+
+            count += 1 + sibling.map_or(0, |sibling| self.subtree_len(sibling));
+            root = parent;
+
+            // Like in a packed memory array, the allowed density of a subtree
+            // grows from 1/2 at the root to 1 at the bottom, so a rebuild
+            // leaves room for a proportional number of further inserts.
+            let depth = BiTree::depth(root);
+            let room = (1 << (max_depth + 1 - depth)) - 1;
+
+            if count * 2 * max_depth <= room * (max_depth + depth) {
+                break;
+            }
+        }
+
+        (root, count)
+    }
+
+    /// The last index on the bottom level of a balanced subtree of `count`
+    /// entries rooted at `root`.
+    fn last_index(root: usize, count: usize) -> usize {
+        let bottom = 1 << (Self::balanced_height(count) - 1);
+
+        (root + 2) * bottom - 2
+    }
+
+    /// Inserts `entry` by rebuilding the subtree at `root`, found by
+    /// `rebuild_root`, with the new entry merged in.
+    ///
+    /// This keeps the depth of every new entry at most `balanced_height(len)`,
+    /// so the capacity stays linear in `len()`.
+    fn insert_rebuilding(&mut self, root: usize, count: usize, entry: (K, V)) -> usize {
+        self.set_len(self.len() + 1);
+
+        let mut entries = self.take_subtree(root);
+        let position = entries
+            .partition_point(|(r_key, _)| self.cmp.compare(r_key, &entry.0) == Ordering::Less);
+        entries.insert(position, entry);
+
+        let last = Self::last_index(root, count);
+
+        while self.capacity() <= last {
+            self.grow_to(self.config.grown_capacity(self.capacity()));
+        }
+
+        self.fill_balanced(root, count, &mut entries.into_iter());
+
+        Self::balanced_index(root, count, position)
+    }
+
+    /// Makes room for another level of entries.
+    ///
+    /// If a balanced tree of the current entries would leave room for one
+    /// more level, the tree is skewed and is rebuilt balanced instead of
+    /// allocating more. Either way indices may change.
+    fn grow(&mut self) {
+        let levels = Self::balanced_height(self.len()) + 1;
+
+        if (1 << levels) - 1 <= self.capacity() {
+            self.rebuild(self.capacity());
+        } else {
+            self.grow_to(self.config.grown_capacity(self.capacity()));
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let index = self.find_index(key);
+
+        if let Some(cell) = self.mem.get(index) {
+            cell.as_ref().map(|kv| &kv.1)
+        } else {
+            None
+        }
+    }
+
+    /// Same as [`get`](Self::get), using a descent that avoids
+    /// data-dependent branches and prefetches ahead.
+    ///
+    /// Whether this beats `get` depends on the hardware and the size of the
+    /// entries; `benches/find.rs` compares the two.
+    pub fn get_fast<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.entry_at(self.find_index_branchless(key))
+            .map(|(_, value)| value)
+    }
+
+    /// Looks up every key of `keys`, returning the values in the same positions.
+    ///
+    /// The keys are visited in sorted order, and each descent resumes from
+    /// the deepest cell of the previous one whose subtree can still hold the
+    /// key, instead of from the root. Batches of nearby keys so skip most of
+    /// the path, which makes up for the sort once batches are large; see the
+    /// `find` bench.
+    pub fn get_many<'a, Q>(&'a self, keys: &[Q]) -> Vec<Option<&'a V>>
+    where
+        K: Borrow<Q>,
+        C: Comparator<Q>,
+    {
+        let mut order: Vec<_> = (0..keys.len()).collect();
+        order.sort_unstable_by(|&a, &b| self.cmp.compare(&keys[a], &keys[b]));
+
+        let mut values = vec![None; keys.len()];
+
+        // The cells where the current path turned left. Their keys bound the
+        // subtrees below them from above, the keys already visited from below.
+        let mut left_turns = Vec::new();
+        let mut index = 0;
+
+        for position in order {
+            let key = &keys[position];
+
+            while let Some(&turn) = left_turns.last() {
+                let (turn_key, _) = self.entry_at(turn).expect("left turns are occupied");
+
+                if self.cmp.compare(key, turn_key.borrow()) == Ordering::Less {
+                    break;
+                }
+
+                index = turn;
+                left_turns.pop();
+            }
+
+            while let Some((r_key, value)) = self.entry_at(index) {
+                let child = match self.cmp.compare(key, r_key.borrow()) {
+                    Ordering::Equal => {
+                        values[position] = Some(value);
+                        break;
+                    }
+                    Ordering::Less => {
+                        left_turns.push(index);
+                        BiTree::left(index)
+                    }
+                    Ordering::Greater => BiTree::right(index),
+                };
+
+                index = child.unwrap_or(NO_CELL);
+            }
+        }
+
+        values
+    }
+
+    /// Returns the stored key along with its value.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.entry_at(self.find_index(key))
+    }
+
+    /// The entry with the greatest key less than or equal to `key`.
+    pub fn floor(&self, key: &K) -> Option<(&K, &V)> {
+        self.entry_at(self.floor_index(key)?)
+    }
+
+    /// The entry with the smallest key greater than or equal to `key`.
+    pub fn ceiling(&self, key: &K) -> Option<(&K, &V)> {
+        self.entry_at(self.ceiling_index(key)?)
+    }
+
+    /// The entry whose key is nearest to `key`, preferring the smaller key on ties.
+    ///
+    /// Picks between the [`floor`](Self::floor) and the
+    /// [`ceiling`](Self::ceiling), so the distances never go negative.
+    pub fn find_closest(&self, key: &K) -> Option<(&K, &V)>
+    where
+        K: Clone + Sub<Output = K> + PartialOrd,
+    {
+        match (self.floor(key), self.ceiling(key)) {
+            (Some(below), Some(above)) => {
+                let to_below = key.clone() - below.0.clone();
+                let to_above = above.0.clone() - key.clone();
+
+                Some(if to_above < to_below { above } else { below })
+            }
+            (below, above) => below.or(above),
+        }
+    }
+
+    /// The entry with the greatest key strictly less than `key`.
+    pub fn predecessor(&self, key: &K) -> Option<(&K, &V)> {
+        self.entry_at(self.predecessor_index(key)?)
+    }
+
+    /// The entry with the smallest key strictly greater than `key`.
+    pub fn successor(&self, key: &K) -> Option<(&K, &V)> {
+        self.entry_at(self.successor_index(key)?)
+    }
+
+    /// Iterates over the entries whose keys lie within `range`, in order.
+    ///
+    /// Only the entries inside the range are visited. A range whose start
+    /// lies after its end yields nothing.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, C, I> {
+        let front = match range.start_bound() {
+            Bound::Included(start) => self.ceiling_index(start),
+            Bound::Excluded(start) => self.successor_index(start),
+            Bound::Unbounded => self.min_index(),
+        };
+
+        let back = match range.end_bound() {
+            Bound::Included(end) => self.floor_index(end),
+            Bound::Excluded(end) => self.predecessor_index(end),
+            Bound::Unbounded => self.max_index(),
+        };
+
+        let bounds = front.zip(back).filter(|&(front, back)| {
+            match (self.entry_at(front), self.entry_at(back)) {
+                (Some((front, _)), Some((back, _))) => {
+                    self.cmp.compare(front, back) != Ordering::Greater
+                }
+                _ => false,
+            }
+        });
+
+        Range { tree: self, bounds }
+    }
+
+    /// The number of keys in `range`.
+    ///
+    /// The tree keeps no subtree sizes, so this walks the range in `O(log n + k)`
+    /// for `k` matching keys.
+    pub fn count_range<R: RangeBounds<K>>(&self, range: R) -> usize {
+        self.range(range).count()
+    }
+
+    /// Iterates over the entries with keys below `upper`, or up to and
+    /// including it if `inclusive`.
+    pub fn headmap(&self, upper: &K, inclusive: bool) -> Range<'_, K, V, C, I> {
+        self.range((Bound::Unbounded, Self::bound(upper, inclusive)))
+    }
+
+    /// Iterates over the entries with keys above `lower`, or from and
+    /// including it if `inclusive`.
+    pub fn tailmap(&self, lower: &K, inclusive: bool) -> Range<'_, K, V, C, I> {
+        self.range((Self::bound(lower, inclusive), Bound::Unbounded))
+    }
+
+    /// Iterates over the entries with keys between `lower` and `upper`, each
+    /// end included as requested.
+    pub fn submap(
+        &self,
+        lower: &K,
+        lower_inclusive: bool,
+        upper: &K,
+        upper_inclusive: bool,
+    ) -> Range<'_, K, V, C, I> {
+        self.range((
+            Self::bound(lower, lower_inclusive),
+            Self::bound(upper, upper_inclusive),
+        ))
+    }
+
+    fn bound(key: &K, inclusive: bool) -> Bound<&K> {
+        if inclusive {
+            Bound::Included(key)
+        } else {
+            Bound::Excluded(key)
+        }
+    }
+
+    /// Removes every entry whose key falls in `range`.
+    ///
+    /// The surviving entries are rebuilt balanced like [`retain`](Self::retain)
+    /// does.
+    pub fn remove_range<R: RangeBounds<K>>(&mut self, range: R) {
+        self.retain_by_range(range, false);
+    }
+
+    /// Keeps only the entries whose keys fall in `range`, the inverse of
+    /// [`remove_range`](Self::remove_range).
+    ///
+    /// The surviving entries are rebuilt balanced like [`retain`](Self::retain)
+    /// does.
+    pub fn retain_range<R: RangeBounds<K>>(&mut self, range: R) {
+        self.retain_by_range(range, true);
+    }
+
+    /// Keeps the entries whose keys are in `range` exactly if `inside`.
+    fn retain_by_range<R: RangeBounds<K>>(&mut self, range: R, inside: bool) {
+        let cmp = &self.cmp;
+
+        let above_start = |key: &K| match range.start_bound() {
+            Bound::Included(start) => cmp.compare(key, start) != Ordering::Less,
+            Bound::Excluded(start) => cmp.compare(key, start) == Ordering::Greater,
+            Bound::Unbounded => true,
+        };
+        let below_end = |key: &K| match range.end_bound() {
+            Bound::Included(end) => cmp.compare(key, end) != Ordering::Greater,
+            Bound::Excluded(end) => cmp.compare(key, end) == Ordering::Less,
+            Bound::Unbounded => true,
+        };
+
+        let doomed = Self::matching_indices(&mut self.mem, |key, _| {
+            (above_start(key) && below_end(key)) != inside
+        });
+
+        self.remove_indices(doomed);
+        self.rebalance_kept();
+    }
+
+    /// Validates the layout, for use in tests and fuzzing.
+    ///
+    /// Checks that every key is ordered against all its ancestors, that no
+    /// entry sits below an empty cell where lookups could never reach it, and
+    /// that `len()` matches the number of entries.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let mut stack = vec![(0, None, None)];
+
+        while let Some((index, low, high)) = stack.pop() {
+            let Some((key, _)) = self.entry_at(index) else {
+                continue;
+            };
+
+            let above_low = low.map_or(true, |low| self.cmp.compare(low, key) == Ordering::Less);
+            let below_high =
+                high.map_or(true, |high| self.cmp.compare(key, high) == Ordering::Less);
+
+            if !above_low || !below_high {
+                return Err(format!("the key at index {} is out of order", index));
+            }
+
+            if let Some(left) = BiTree::left(index) {
+                stack.push((left, low, Some(key)));
+            }
+            if let Some(right) = BiTree::right(index) {
+                stack.push((right, Some(key), high));
+            }
+        }
+
+        if let Some(index) = self.first_unreachable() {
+            return Err(format!(
+                "the entry at index {} is below an empty cell",
+                index
+            ));
+        }
+
+        let occupied = self.mem.iter().flatten().count();
+
+        if occupied != self.len() {
+            return Err(format!(
+                "len() is {} but {} cells are occupied",
+                self.len(),
+                occupied
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The number of keys strictly less than `key`.
+    ///
+    /// Counts through `range`, so this is `O(rank + log n)`.
+    pub fn rank(&self, key: &K) -> usize {
+        self.range(..key).count()
+    }
+
+    /// The index of the entry with the greatest key less than or equal to `key`.
+    fn floor_index(&self, key: &K) -> Option<usize> {
+        let mut index = 0;
+        let mut best = None;
+
+        while let Some((r_key, _)) = self.entry_at(index) {
+            match self.cmp.compare(key, r_key) {
+                Ordering::Equal => return Some(index),
+                Ordering::Less => index = BiTree::left(index).unwrap_or(NO_CELL),
+                Ordering::Greater => {
+                    // r_key is a candidate, look for a larger one
+                    best = Some(index);
+                    index = BiTree::right(index).unwrap_or(NO_CELL);
+                }
+            }
+        }
+
+        best
+    }
+
+    /// The index of the entry with the smallest key greater than or equal to `key`.
+    fn ceiling_index(&self, key: &K) -> Option<usize> {
+        let mut index = 0;
+        let mut best = None;
+
+        while let Some((r_key, _)) = self.entry_at(index) {
+            match self.cmp.compare(key, r_key) {
+                Ordering::Equal => return Some(index),
+                Ordering::Less => {
+                    // r_key is a candidate, look for a smaller one
+                    best = Some(index);
+                    index = BiTree::left(index).unwrap_or(NO_CELL);
+                }
+                Ordering::Greater => index = BiTree::right(index).unwrap_or(NO_CELL),
+            }
+        }
+
+        best
+    }
+
+    /// The index of the entry with the greatest key strictly less than `key`.
+    fn predecessor_index(&self, key: &K) -> Option<usize> {
+        let mut index = 0;
+        let mut best = None;
+
+        while let Some((r_key, _)) = self.entry_at(index) {
+            if self.cmp.compare(r_key, key) == Ordering::Less {
+                best = Some(index);
+                index = BiTree::right(index).unwrap_or(NO_CELL);
+            } else {
+                index = BiTree::left(index).unwrap_or(NO_CELL);
+            }
+        }
+
+        best
+    }
+
+    /// The index of the entry with the smallest key strictly greater than `key`.
+    fn successor_index(&self, key: &K) -> Option<usize> {
+        let mut index = 0;
+        let mut best = None;
+
+        while let Some((r_key, _)) = self.entry_at(index) {
+            if self.cmp.compare(r_key, key) == Ordering::Greater {
+                best = Some(index);
+                index = BiTree::left(index).unwrap_or(NO_CELL);
+            } else {
+                index = BiTree::right(index).unwrap_or(NO_CELL);
+            }
+        }
+
+        best
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let index = self.find_index(key);
+
+        self.mem
+            .get_mut(index)
+            .and_then(|cell| cell.as_mut())
+            .map(|kv| &mut kv.1)
+    }
+
+    /// Returns mutable references to the values of two distinct keys at once.
+    ///
+    /// Returns `None` if either key is missing or both name the same entry.
+    pub fn get_disjoint_mut<Q>(&mut self, k1: &Q, k2: &Q) -> Option<(&mut V, &mut V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let first = self.find_index(k1);
+        let second = self.find_index(k2);
+
+        if first == second || !self.is_occupied(first) || !self.is_occupied(second) {
+            return None;
+        }
+
+        // Split between the two cells so the borrows are disjoint.
+        let (low, high) = self.mem.split_at_mut(first.max(second));
+        let low = &mut low[first.min(second)].as_mut()?.1;
+        let high = &mut high[0].as_mut()?.1;
+
+        if first < second {
+            Some((low, high))
+        } else {
+            Some((high, low))
+        }
+    }
+
+    /// Applies `f` to the value of `key`, returning whether the key was found.
+    ///
+    /// `f` is not called if the key is absent.
+    pub fn update<Q, F>(&mut self, key: &Q, f: F) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+        F: FnOnce(&mut V),
+    {
+        self.get_mut(key).map(f).is_some()
+    }
+
+    /// Overwrites the value of an existing `key`, returning the old value.
+    ///
+    /// Unlike [`insert`](Self::insert), an absent key is not added: the tree
+    /// is left unchanged and `value` is dropped.
+    pub fn replace<Q>(&mut self, key: &Q, value: V) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.get_mut(key).map(|old| core::mem::replace(old, value))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        self.is_occupied(self.find_index(key))
+    }
+
+    /// The level `key` sits on, counting the root as 0.
+    ///
+    /// This is the number of branches a lookup of `key` takes.
+    pub fn depth_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let index = self.find_index(key);
+
+        self.is_occupied(index).then(|| BiTree::depth(index))
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let index = self.find_index(key);
+
+        if self.is_occupied(index) {
+            Some(self.remove_at(index).1)
+        } else {
+            None
+        }
+    }
+
+    /// Removes the entry of `key` like [`remove`](Self::remove), then
+    /// compacts the array with [`shrink_to_fit`](Self::shrink_to_fit) once
+    /// the occupancy drops below the shrink threshold, a quarter by default.
+    ///
+    /// The threshold is set with [`Builder::shrink_threshold`].
+    pub fn remove_and_maybe_shrink<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Comparator<Q>,
+    {
+        let value = self.remove(key)?;
+
+        if self.config.should_shrink(self.len(), self.capacity()) {
+            self.shrink_to_fit();
+        }
+
+        Some(value)
+    }
+}
+
+/// Stands in for a child whose index would overflow. No array can reach
+/// it, so descents treat it as an empty cell below the tree.
+const NO_CELL: usize = usize::MAX;
+
+/// The children of a cell in a balanced layout lie within the array.
+const BALANCED_FITS: &str = "a balanced layout fits into the array";
+
+/// Hints the CPU to pull the cache line at `ptr` in. A no-op where there is
+/// no stable prefetch intrinsic.
+#[inline(always)]
+fn prefetch<T>(ptr: *const T) {
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: prefetching never faults, the address need not be valid.
+    unsafe {
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+        _mm_prefetch::<_MM_HINT_T0>(ptr.cast());
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = ptr;
+}
+
+#[allow(non_snake_case)]
+mod BiTree {
+    use crate::Index;
+
+    pub fn is_right(index: usize) -> bool {
+        index % 2 == 0
+    }
+
+    pub fn parrent(index: usize) -> Option<usize> {
+        if index > 0 {
+            Some(if is_right(index) {
+                (index - 2) / 2
+            } else {
+                (index - 1) / 2
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The level of `index`, the root being at depth 0.
+    pub fn depth(index: usize) -> usize {
+        index.checked_add(1).map_or(usize::BITS, |position| {
+            usize::BITS - 1 - position.leading_zeros()
+        }) as usize
+    }
+
+    /// The right child of `index`, or `None` if its index would overflow.
+    pub fn right<I: Index>(index: I) -> Option<I> {
+        index.right()
+    }
+
+    /// The left child of `index`, or `None` if its index would overflow.
+    pub fn left<I: Index>(index: I) -> Option<I> {
+        index.left()
+    }
+}
+
+/// Looks up the value of a key, panicking if it is absent.
+impl<K, V, C, I, Q> core::ops::Index<&Q> for BinaryTree<K, V, C, I>
+where
+    K: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<K> + Comparator<Q>,
+    I: Index,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl<K: Ord, V, I: Index> FromIterator<(K, V)> for BinaryTree<K, V, Natural, I> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let iter = iter.into_iter();
+
+        let mut tree = Self::with_capacity_and_comparator(iter.size_hint().0, Natural);
+
+        for (key, value) in iter {
+            tree.insert(key, value);
+        }
+
+        tree
+    }
+}
+
+/// Later duplicates of a key overwrite earlier ones, like repeated inserts.
+impl<K: Ord, V, const N: usize> From<[(K, V); N]> for BinaryTree<K, V> {
+    fn from(items: [(K, V); N]) -> Self {
+        items.into_iter().collect()
+    }
+}
+
+/// Builds a balanced tree, failing if a key occurs more than once.
+impl<K: Ord, V> TryFrom<Vec<(K, V)>> for BinaryTree<K, V> {
+    type Error = DuplicateKeyError<K>;
+
+    fn try_from(mut items: Vec<(K, V)>) -> Result<Self, Self::Error> {
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+
+        if let Some(position) = items.windows(2).position(|w| w[0].0 == w[1].0) {
+            let (key, _) = items.swap_remove(position);
+
+            return Err(DuplicateKeyError { key });
+        }
+
+        let mut tree = Self::with_capacity(1);
+
+        tree.replace_sorted(items.len(), items);
+
+        Ok(tree)
+    }
+}
+
+/// Builds a balanced tree straight from the sorted entries of the map.
+impl<K: Ord, V> From<BTreeMap<K, V>> for BinaryTree<K, V> {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        let mut tree = Self::with_capacity(1);
+
+        tree.replace_sorted(map.len(), map);
+
+        tree
+    }
+}
+
+impl<K: Ord, V> From<BinaryTree<K, V>> for BTreeMap<K, V> {
+    fn from(tree: BinaryTree<K, V>) -> Self {
+        tree.into_iter().collect()
+    }
+}
+
+impl<K, V, C: Comparator<K>, I: Index> Extend<(K, V)> for BinaryTree<K, V, C, I> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+
+        self.reserve(iter.size_hint().0);
+
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<'a, K, V, C, I> Extend<(&'a K, &'a V)> for BinaryTree<K, V, C, I>
+where
+    K: Copy,
+    V: Copy,
+    C: Comparator<K>,
+    I: Index,
+{
+    fn extend<T: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().map(|(key, value)| (*key, *value)));
+    }
+}
+
+impl<K, V, C, I: Index> IntoIterator for BinaryTree<K, V, C, I> {
+    type Item = (K, V);
+    type IntoIter = BinaryTreeIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Self::IntoIter {
+            indexer: BiTreeIndexIter::new(self.capacity()),
+            remaining: self.len(),
+            mem: self.mem,
+        }
+    }
+}
+
+impl<'a, K, V, C, I: Index> IntoIterator for &'a BinaryTree<K, V, C, I> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, C, I: Index> IntoIterator for &'a mut BinaryTree<K, V, C, I> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+///
+/// Iterates over a BinaryTree in order.
+///
+pub struct BinaryTreeIter<K, V> {
+    mem: Box<[Option<(K, V)>]>,
+    indexer: BiTreeIndexIter,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for BinaryTreeIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for index in self.indexer.by_ref() {
+            // SAFETY: BiTreeIndexIter is limited to the capacity of mem
+            if let Some(res) = unsafe { self.mem.get_unchecked_mut(index) }.take() {
+                self.remaining -= 1;
+                return Some(res);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for BinaryTreeIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(index) = self.indexer.next_back() {
+            // SAFETY: BiTreeIndexIter is limited to the capacity of mem
+            if let Some(res) = unsafe { self.mem.get_unchecked_mut(index) }.take() {
+                self.remaining -= 1;
+                return Some(res);
+            }
+        }
+        None
+    }
+}
+
+impl<K, V> ExactSizeIterator for BinaryTreeIter<K, V> {}
+
+impl<K, V> FusedIterator for BinaryTreeIter<K, V> {}
+
+///
+/// Iterates over references to the entries of a BinaryTree in order.
+///
+pub struct Iter<'a, K, V> {
+    mem: &'a [Option<(K, V)>],
+    indexer: BiTreeIndexIter,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for index in self.indexer.by_ref() {
+            if let Some(Some((key, value))) = self.mem.get(index) {
+                self.remaining -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(index) = self.indexer.next_back() {
+            if let Some(Some((key, value))) = self.mem.get(index) {
+                self.remaining -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+///
+/// Iterates over the entries of a BinaryTree in order, with mutable references to the values.
+///
+pub struct IterMut<'a, K, V> {
+    mem: *mut Option<(K, V)>,
+    indexer: BiTreeIndexIter,
+    remaining: usize,
+    _marker: PhantomData<&'a mut [Option<(K, V)>]>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for index in self.indexer.by_ref() {
+            // SAFETY: BiTreeIndexIter is limited to the capacity of the borrowed memory
+            // and yields every index at most once, so the references never alias.
+            if let Some((key, value)) = unsafe { &mut *self.mem.add(index) } {
+                self.remaining -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(index) = self.indexer.next_back() {
+            // SAFETY: see `next`
+            if let Some((key, value)) = unsafe { &mut *self.mem.add(index) } {
+                self.remaining -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+// SAFETY: IterMut stands for a `&mut [Option<(K, V)>]` that hands out `&K`
+// and `&mut V`, so moving it to another thread is sound exactly if those can
+// be sent there.
+unsafe impl<K: Sync, V: Send> Send for IterMut<'_, K, V> {}
+
+// SAFETY: a shared IterMut hands out no references at all, so sharing it is
+// sound whenever the keys and values can be shared, as for `&mut [(K, V)]`.
+unsafe impl<K: Sync, V: Sync> Sync for IterMut<'_, K, V> {}
+
+///
+/// Iterates over the keys of a BinaryTree in order.
+///
+pub struct Keys<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|kv| kv.0)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Keys<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|kv| kv.0)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+///
+/// Iterates over the values of a BinaryTree in the order of their keys.
+///
+pub struct Values<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|kv| kv.1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Values<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|kv| kv.1)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+///
+/// Iterates over mutable references to the values of a BinaryTree in the order of their keys.
+///
+pub struct ValuesMut<'a, K, V>(IterMut<'a, K, V>);
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|kv| kv.1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for ValuesMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|kv| kv.1)
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+///
+/// Iterates over the owned keys of a BinaryTree in order.
+///
+pub struct IntoKeys<K, V>(BinaryTreeIter<K, V>);
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|kv| kv.0)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoKeys<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|kv| kv.0)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {}
+
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+
+///
+/// Iterates over the owned values of a BinaryTree in the order of their keys.
+///
+pub struct IntoValues<K, V>(BinaryTreeIter<K, V>);
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|kv| kv.1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoValues<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|kv| kv.1)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {}
+
+impl<K, V> FusedIterator for IntoValues<K, V> {}
+
+///
+/// Removes the entries of a BinaryTree in order.
+///
+pub struct Drain<'a, K, V> {
+    entries: vec::IntoIter<(K, V)>,
+    _marker: PhantomData<&'a mut [Option<(K, V)>]>,
+}
+
+impl<K, V> Iterator for Drain<'_, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Drain<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Drain<'_, K, V> {}
+
+impl<K, V> FusedIterator for Drain<'_, K, V> {}
+
+///
+/// Removes the entries of a BinaryTree matching a predicate in order.
+///
+pub struct ExtractIf<'a, K, V, F, C = Natural, I: Index = usize> {
+    tree: &'a mut BinaryTree<K, V, C, I>,
+    /// The entries not yet visited, with the indices they were taken from.
+    entries: vec::IntoIter<(usize, (K, V))>,
+    kept: Vec<(usize, (K, V))>,
+    /// The indices the extracted entries were taken from.
+    extracted: Vec<usize>,
+    pred: F,
+}
+
+impl<K, V, F, C, I> Iterator for ExtractIf<'_, K, V, F, C, I>
+where
+    F: FnMut(&K, &mut V) -> bool,
+    I: Index,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, (key, mut value)) in self.entries.by_ref() {
+            if (self.pred)(&key, &mut value) {
+                self.extracted.push(index);
+                return Some((key, value));
+            }
+
+            self.kept.push((index, (key, value)));
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.entries.len()))
+    }
+}
+
+impl<K, V, F, C, I> FusedIterator for ExtractIf<'_, K, V, F, C, I>
+where
+    F: FnMut(&K, &mut V) -> bool,
+    I: Index,
+{
+}
+
+impl<K, V, F, C, I: Index> Drop for ExtractIf<'_, K, V, F, C, I> {
+    fn drop(&mut self) {
+        let kept = core::mem::take(&mut self.kept);
+        let len = kept.len() + self.entries.len();
+
+        // Every entry goes back to its cell, so only the extracted ones leave
+        // holes to fill, and nothing here can run out of capacity.
+        for (index, entry) in kept.into_iter().chain(self.entries.by_ref()) {
+            self.tree.mem[index] = Some(entry);
+        }
+
+        self.tree.set_len(len);
+        self.tree.fill_holes(core::mem::take(&mut self.extracted));
+        self.tree.rebalance_kept();
+    }
+}
+
+///
+/// Iterates over the entries of a BinaryTree within a range of keys in order.
+///
+pub struct Range<'a, K, V, C = Natural, I = usize> {
+    tree: &'a BinaryTree<K, V, C, I>,
+    /// The indices of the next entries to yield from the front and the back.
+    bounds: Option<(usize, usize)>,
+}
+
+impl<'a, K, V, C, I: Index> Iterator for Range<'a, K, V, C, I> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (front, back) = self.bounds?;
+
+        self.bounds = if front == back {
+            None
+        } else {
+            self.tree.next_index(front).map(|front| (front, back))
+        };
+
+        self.tree.entry_at(front)
+    }
+}
+
+impl<K, V, C, I: Index> DoubleEndedIterator for Range<'_, K, V, C, I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (front, back) = self.bounds?;
+
+        self.bounds = if front == back {
+            None
+        } else {
+            self.tree.prev_index(back).map(|back| (front, back))
+        };
+
+        self.tree.entry_at(back)
+    }
+}
+
+impl<K, V, C, I: Index> FusedIterator for Range<'_, K, V, C, I> {}
+
+///
+/// Iterates over references to the entries of a BinaryTree in level order.
+///
+pub struct Bfs<'a, K, V> {
+    cells: core::slice::Iter<'a, Option<(K, V)>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Bfs<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.cells.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Bfs<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.cells.by_ref().rev().flatten().next()?;
+        self.remaining -= 1;
+        Some((key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Bfs<'_, K, V> {}
+
+impl<K, V> FusedIterator for Bfs<'_, K, V> {}
+
+///
+/// Iterates over references to the entries of a BinaryTree in pre-order.
+///
+pub struct PreOrder<'a, K, V> {
+    mem: &'a [Option<(K, V)>],
+    /// The roots of the subtrees still to visit, the next one on top.
+    stack: Vec<usize>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for PreOrder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(index) = self.stack.pop() {
+            if let Some(Some((key, value))) = self.mem.get(index) {
+                self.stack.extend(BiTree::right(index));
+                self.stack.extend(BiTree::left(index));
+                self.remaining -= 1;
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for PreOrder<'_, K, V> {}
+
+impl<K, V> FusedIterator for PreOrder<'_, K, V> {}
+
+///
+/// Iterates over references to the entries of a BinaryTree in post-order.
+///
+pub struct PostOrder<'a, K, V> {
+    mem: &'a [Option<(K, V)>],
+    /// The nodes still to visit, the next one on top, flagged once their
+    /// children are on the stack above them.
+    stack: Vec<(usize, bool)>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for PostOrder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((index, visited)) = self.stack.pop() {
+            if let Some(Some((key, value))) = self.mem.get(index) {
+                if visited {
+                    self.remaining -= 1;
+                    return Some((key, value));
+                }
+
+                self.stack.push((index, true));
+                self.stack
+                    .extend(BiTree::right(index).map(|right| (right, false)));
+                self.stack
+                    .extend(BiTree::left(index).map(|left| (left, false)));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for PostOrder<'_, K, V> {}
+
+impl<K, V> FusedIterator for PostOrder<'_, K, V> {}
+
+/// Yields the indices below `capacity` in order.
+///
+/// Walking from the front descends left-first, walking from the back
+/// descends right-first. `remaining` counts the indices not yet yielded from
+/// either end, so the two walks stop once they meet.
+struct BiTreeIndexIter {
+    capacity: usize,
+    remaining: usize,
+    stack: Vec<usize>,
+    current: Option<usize>,
+    back_stack: Vec<usize>,
+    back_current: Option<usize>,
+}
+
+impl BiTreeIndexIter {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            remaining: capacity,
+            stack: Vec::new(),
+            current: Some(0),
+            back_stack: Vec::new(),
+            back_current: Some(0),
+        }
+    }
+
+    fn left(&self, node: usize) -> Option<usize> {
+        BiTree::left(node).filter(|&index| index < self.capacity)
+    }
+
+    fn right(&self, node: usize) -> Option<usize> {
+        BiTree::right(node).filter(|&index| index < self.capacity)
+    }
+}
+
+impl Iterator for BiTreeIndexIter {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        loop {
+            if let Some(node) = self.current {
+                self.stack.push(node);
+                self.current = self.left(node);
+            } else if let Some(node) = self.stack.pop() {
+                self.current = self.right(node);
+                self.remaining -= 1;
+                return Some(node);
+            } else {
+                return None;
+            }
+        }
+    }
+}
+
+impl DoubleEndedIterator for BiTreeIndexIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        loop {
+            if let Some(node) = self.back_current {
+                self.back_stack.push(node);
+                self.back_current = self.right(node);
+            } else if let Some(node) = self.back_stack.pop() {
+                self.back_current = self.left(node);
+                self.remaining -= 1;
+                return Some(node);
+            } else {
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A degenerate tree of `n` entries where every node is the right child
+    /// of its parent.
+    fn right_spine(n: usize) -> BinaryTree<usize, ()> {
+        let mut b_tree = BinaryTree::with_capacity(1 << n);
+        let mut index = 0;
+
+        for key in 0..n {
+            b_tree.mem[index] = Some((key, ()));
+            index = BiTree::right(index).unwrap();
+        }
+
+        b_tree.set_len(n);
+        b_tree
+    }
+
+    #[test]
+    fn it_works() {
+        let mut b_tree = BinaryTree::with_capacity(8);
+
+        b_tree.insert(7, "sieben");
+        b_tree.insert(4, "vier");
+        b_tree.insert(2, "zwei");
+        b_tree.insert(5, "fünf");
+
+        assert_eq!(b_tree.get(&2), Some("zwei").as_ref());
+        assert_eq!(b_tree.get(&5), Some("fünf").as_ref());
+    }
+
+    #[test]
+    fn balancing(){
+        let mut b_tree = BinaryTree::with_capacity(8);
+
+        b_tree.insert(1, "eins");
+        println!("{:#?}", b_tree);
+        b_tree.insert(2, "zwei");
+        println!("{:#?}", b_tree);
+        b_tree.insert(3, "drei");
+        println!("{:#?}", b_tree);
+        b_tree.insert(4, "vier");
+        println!("{:#?}", b_tree);
+        b_tree.insert(5, "fünf");
+        println!("{:#?}", b_tree);
+        b_tree.insert(6, "sechs");
+        println!("{:#?}", b_tree);
+        b_tree.insert(7, "sieben");
+        println!("{:#?}", b_tree);
+
+        
+        let vec: Vec<_> = b_tree.into_iter().map(|kv| kv.0).collect();
+
+        assert_eq!(vec, vec![1,2,3,4,5,6,7])
+
+    }
+
+    #[test]
+    fn it_grows() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        b_tree.insert(7, "sieben");
+        b_tree.insert(4, "vier");
+        b_tree.insert(2, "zwei");
+        b_tree.insert(5, "fünf");
+
+        assert_eq!(b_tree.get(&2), Some("zwei").as_ref());
+        assert_eq!(b_tree.get(&5), Some("fünf").as_ref());
+    }
+
+    #[test]
+    fn iter_it() {
+        let mut b_tree = BinaryTree::with_capacity(32);
+
+        b_tree.insert(7, "sieben");
+        b_tree.insert(4, "vier");
+        b_tree.insert(2, "zwei");
+        b_tree.insert(5, "fünf");
+
+        let vec: Vec<_> = b_tree.into_iter().map(|kv| kv.0).collect();
+
+        assert_eq!(vec, vec![2, 4, 5, 7])
+    }
+
+    #[test]
+    fn contains_key() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        b_tree.insert(7, "sieben");
+        b_tree.insert(4, "vier");
+
+        assert!(b_tree.contains_key(&7));
+        assert!(b_tree.contains_key(&4));
+        assert!(!b_tree.contains_key(&5));
+        // lands past capacity()
+        assert!(!b_tree.contains_key(&2));
+    }
+
+    #[test]
+    fn len() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        assert!(b_tree.is_empty());
+
+        b_tree.insert(7, "sieben");
+        b_tree.insert(4, "vier");
+        b_tree.insert(2, "zwei");
+        b_tree.insert(5, "fünf");
+
+        assert_eq!(b_tree.len(), 4);
+        assert!(!b_tree.is_empty());
+
+        // overwriting does not add an entry
+        b_tree.insert(5, "FÜNF");
+
+        assert_eq!(b_tree.len(), 4);
+
+        b_tree.remove(&4);
+        b_tree.remove(&3);
+
+        assert_eq!(b_tree.len(), 3);
+    }
+
+    #[test]
+    fn clear() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        b_tree.insert(7, "sieben");
+        b_tree.insert(4, "vier");
+        b_tree.insert(2, "zwei");
+
+        let capacity = b_tree.capacity();
+
+        b_tree.clear();
+
+        assert_eq!(b_tree.len(), 0);
+        assert_eq!(b_tree.get(&7), None);
+        assert_eq!(b_tree.get(&2), None);
+        assert_eq!(b_tree.capacity(), capacity);
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut b_tree = BinaryTree::with_capacity(8);
+
+        b_tree.insert(7, 70);
+        b_tree.insert(4, 40);
+
+        *b_tree.get_mut(&4).unwrap() += 2;
+
+        assert_eq!(b_tree.get(&4), Some(&42));
+        assert_eq!(b_tree.get_mut(&5), None);
+    }
+
+    #[test]
+    fn get_key_value() {
+        /// Only `id` takes part in the ordering.
+        #[derive(Debug)]
+        struct Tagged {
+            id: u32,
+            tag: &'static str,
+        }
+
+        impl PartialEq for Tagged {
+            fn eq(&self, other: &Self) -> bool {
+                self.id == other.id
+            }
+        }
+
+        impl Eq for Tagged {}
+
+        impl PartialOrd for Tagged {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for Tagged {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.id.cmp(&other.id)
+            }
+        }
+
+        let mut b_tree = BinaryTree::with_capacity(8);
+
+        b_tree.insert(
+            Tagged {
+                id: 7,
+                tag: "stored",
+            },
+            "sieben",
+        );
+
+        let (key, value) = b_tree
+            .get_key_value(&Tagged {
+                id: 7,
+                tag: "lookup",
+            })
+            .unwrap();
+
+        assert_eq!(key.tag, "stored");
+        assert_eq!(*value, "sieben");
+        assert_eq!(b_tree.get_key_value(&Tagged { id: 4, tag: "" }), None);
+    }
+
+    #[test]
+    fn iter_borrowed() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        b_tree.insert(7, "sieben");
+        b_tree.insert(4, "vier");
+        b_tree.insert(2, "zwei");
+        b_tree.insert(5, "fünf");
+
+        let first: Vec<_> = b_tree.iter().collect();
+        let second: Vec<_> = b_tree.iter().collect();
+
+        assert_eq!(
+            first,
+            vec![(&2, &"zwei"), (&4, &"vier"), (&5, &"fünf"), (&7, &"sieben")]
+        );
+        assert_eq!(first, second);
+        assert_eq!(b_tree.len(), 4);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        b_tree.insert(7, 70);
+        b_tree.insert(4, 40);
+        b_tree.insert(2, 20);
+        b_tree.insert(5, 50);
+
+        for (_, value) in b_tree.iter_mut() {
+            *value *= 2;
+        }
+
+        let vec: Vec<_> = b_tree.iter().map(|(k, v)| (*k, *v)).collect();
+
+        assert_eq!(vec, vec![(2, 40), (4, 80), (5, 100), (7, 140)]);
+    }
+
+    #[test]
+    fn keys_and_values() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        b_tree.insert(7, "sieben");
+        b_tree.insert(4, "vier");
+        b_tree.insert(2, "zwei");
+        b_tree.insert(5, "fünf");
+
+        let keys: Vec<_> = b_tree.keys().copied().collect();
+        let values: Vec<_> = b_tree.values().copied().collect();
+
+        assert_eq!(keys, vec![2, 4, 5, 7]);
+        assert_eq!(values, vec!["zwei", "vier", "fünf", "sieben"]);
+    }
+
+    #[test]
+    fn values_mut() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        b_tree.insert(7, 7);
+        b_tree.insert(4, 4);
+        b_tree.insert(2, 2);
+        b_tree.insert(5, 5);
+
+        b_tree.values_mut().for_each(|value| *value += 1);
+
+        let values: Vec<_> = b_tree.values().copied().collect();
+
+        assert_eq!(values, vec![3, 5, 6, 8]);
+    }
+
+    #[test]
+    fn iter_rev() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        for key in [7, 4, 2, 5, 9, 8] {
+            b_tree.insert(key, key);
+        }
+
+        let forward: Vec<_> = b_tree.keys().copied().collect();
+        let mut backward: Vec<_> = b_tree.keys().rev().copied().collect();
+        backward.reverse();
+
+        assert_eq!(forward, backward);
+
+        let owned: Vec<_> = b_tree.clone().into_iter().rev().map(|kv| kv.0).collect();
+
+        assert_eq!(owned, vec![9, 8, 7, 5, 4, 2]);
+    }
+
+    #[test]
+    fn iter_meets_in_the_middle() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        for key in [7, 4, 2, 5, 9, 8] {
+            b_tree.insert(key, key);
+        }
+
+        let mut iter = b_tree.iter();
+        let mut seen = Vec::new();
+
+        while let Some((front, _)) = iter.next() {
+            seen.push(*front);
+
+            if let Some((back, _)) = iter.next_back() {
+                seen.push(*back);
+            }
+        }
+
+        seen.sort();
+
+        assert_eq!(seen, vec![2, 4, 5, 7, 8, 9]);
+    }
+
+    #[test]
+    fn iter_len() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        for key in [7, 4, 2, 5, 9, 8] {
+            b_tree.insert(key, key);
+        }
+
+        let mut iter = b_tree.iter();
+
+        assert_eq!(iter.len(), 6);
+
+        iter.next();
+        iter.next_back();
+
+        assert_eq!(iter.len(), 4);
+        assert_eq!(b_tree.keys().skip(1).len(), 5);
+
+        let mut owned = b_tree.into_iter();
+        owned.next();
+
+        assert_eq!(owned.len(), 5);
+    }
+
+    #[test]
+    fn iter_fused() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        b_tree.insert(7, "sieben");
+        b_tree.insert(4, "vier");
+
+        let mut iter = b_tree.iter();
+
+        assert!(iter.next().is_some());
+        assert!(iter.next().is_some());
+
+        for _ in 0..4 {
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next_back(), None);
+        }
+
+        let mut owned = b_tree.into_iter();
+        owned.by_ref().for_each(drop);
+
+        for _ in 0..4 {
+            assert_eq!(owned.next(), None);
+        }
+    }
+
+    #[test]
+    fn from_iter() {
+        let pairs = vec![(7, "sieben"), (4, "vier"), (2, "zwei"), (4, "VIER")];
+
+        let b_tree: BinaryTree<_, _> = pairs.into_iter().collect();
+
+        let vec: Vec<_> = b_tree.into_iter().collect();
+
+        assert_eq!(vec, vec![(2, "zwei"), (4, "VIER"), (7, "sieben")]);
+    }
+
+    #[test]
+    fn default() {
+        #[derive(Default)]
+        struct Holder {
+            tree: BinaryTree<i32, i32>,
+        }
+
+        assert_eq!(BinaryTree::<i32, i32>::default().capacity(), 8);
+
+        let holder = Holder::default();
+
+        assert!(holder.tree.is_empty());
+    }
+
+    #[test]
+    fn from_sorted_slice() {
+        for n in 0..40 {
+            let items: Vec<_> = (0..n).map(|key| (key, key * 10)).collect();
+
+            let b_tree = BinaryTree::from_sorted_slice(&items);
+
+            assert_eq!(b_tree.height(), (n as f64 + 1.0).log2().ceil() as usize);
+            assert_eq!(b_tree.len(), items.len());
+            assert_eq!(b_tree.into_iter().collect::<Vec<_>>(), items);
+        }
+    }
+
+    #[test]
+    fn min_max() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        assert_eq!(BinaryTree::min(&b_tree), None);
+        assert_eq!(BinaryTree::max(&b_tree), None);
+
+        for key in [13, 4, 27, 1, 9, 30, 18, 2, 22] {
+            b_tree.insert(key, key * 10);
+        }
+
+        assert_eq!(BinaryTree::min(&b_tree), Some((&1, &10)));
+        assert_eq!(BinaryTree::max(&b_tree), Some((&30, &300)));
+    }
+
+    #[test]
+    fn first_last() {
+        let mut b_tree: BinaryTree<_, _> = [5, 2, 8, 1, 9, 3]
+            .into_iter()
+            .map(|key| (key, ()))
+            .collect();
+
+        assert_eq!(b_tree.first_key_value(), Some((&1, &())));
+        assert_eq!(b_tree.last_key_value(), Some((&9, &())));
+        assert_eq!(b_tree.pop_last(), Some((9, ())));
+
+        let mut drained = Vec::new();
+
+        while let Some((key, ())) = b_tree.pop_first() {
+            assert_eq!(b_tree.check_invariants(), Ok(()));
+            drained.push(key);
+        }
+
+        assert_eq!(drained, vec![1, 2, 3, 5, 8]);
+        assert!(b_tree.is_empty());
+        assert_eq!(b_tree.first_key_value(), None);
+        assert_eq!(b_tree.pop_last(), None);
+    }
+
+    #[test]
+    fn find_closest() {
+        let b_tree: BinaryTree<u32, _> =
+            [10, 20, 40, 80].into_iter().map(|key| (key, ())).collect();
+
+        assert_eq!(b_tree.find_closest(&20), Some((&20, &())));
+        assert_eq!(b_tree.find_closest(&24), Some((&20, &())));
+        assert_eq!(b_tree.find_closest(&36), Some((&40, &())));
+        assert_eq!(b_tree.find_closest(&30), Some((&20, &())));
+        assert_eq!(b_tree.find_closest(&0), Some((&10, &())));
+        assert_eq!(b_tree.find_closest(&1000), Some((&80, &())));
+        assert_eq!(BinaryTree::<u32, ()>::new().find_closest(&5), None);
+    }
+
+    #[test]
+    fn first_last_entry() {
+        let mut b_tree: BinaryTree<_, _> = (1..=6).map(|key| (key, key * 10)).collect();
+
+        *b_tree.first_entry().unwrap().get_mut() += 1;
+
+        let last = b_tree.last_entry().unwrap();
+        assert_eq!(last.key(), &6);
+        assert_eq!(last.remove_entry(), (6, 60));
+
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert_eq!(b_tree.len(), 5);
+        assert_eq!(b_tree.first_key_value(), Some((&1, &11)));
+        assert_eq!(b_tree.last_key_value(), Some((&5, &50)));
+
+        assert!(BinaryTree::<i32, i32>::new().first_entry().is_none());
+    }
+
+    #[test]
+    fn floor_ceiling() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        for key in [40, 20, 60, 10, 30, 50, 70] {
+            b_tree.insert(key, ());
+        }
+
+        assert_eq!(b_tree.floor(&30), Some((&30, &())));
+        assert_eq!(b_tree.floor(&35), Some((&30, &())));
+        assert_eq!(b_tree.floor(&45), Some((&40, &())));
+        assert_eq!(b_tree.floor(&99), Some((&70, &())));
+        assert_eq!(b_tree.floor(&5), None);
+
+        assert_eq!(b_tree.ceiling(&30), Some((&30, &())));
+        assert_eq!(b_tree.ceiling(&35), Some((&40, &())));
+        assert_eq!(b_tree.ceiling(&45), Some((&50, &())));
+        assert_eq!(b_tree.ceiling(&5), Some((&10, &())));
+        assert_eq!(b_tree.ceiling(&99), None);
+    }
+
+    #[test]
+    fn predecessor_successor() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        for key in [40, 20, 60, 10, 30, 50, 70] {
+            b_tree.insert(key, ());
+        }
+
+        assert_eq!(b_tree.predecessor(&30), Some((&20, &())));
+        assert_eq!(b_tree.predecessor(&45), Some((&40, &())));
+        assert_eq!(b_tree.predecessor(&10), None);
+
+        assert_eq!(b_tree.successor(&30), Some((&40, &())));
+        assert_eq!(b_tree.successor(&45), Some((&50, &())));
+        assert_eq!(b_tree.successor(&70), None);
+    }
+
+    #[test]
+    fn range() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        for key in [5, 2, 8, 1, 3, 7, 9, 4, 6] {
+            b_tree.insert(key, ());
+        }
+
+        let keys = |range: Range<'_, i32, ()>| range.map(|kv| *kv.0).collect::<Vec<_>>();
+
+        assert_eq!(keys(b_tree.range(3..=7)), vec![3, 4, 5, 6, 7]);
+        assert_eq!(keys(b_tree.range(3..7)), vec![3, 4, 5, 6]);
+        assert_eq!(keys(b_tree.range(..3)), vec![1, 2]);
+        assert_eq!(keys(b_tree.range(8..)), vec![8, 9]);
+        assert_eq!(keys(b_tree.range(..)), vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(
+            keys(b_tree.range((Bound::Excluded(3), Bound::Excluded(6)))),
+            vec![4, 5]
+        );
+        assert_eq!(keys(b_tree.range(10..)), Vec::<i32>::new());
+        assert_eq!(
+            keys(b_tree.range((Bound::Included(6), Bound::Excluded(4)))),
+            Vec::<i32>::new()
+        );
+        assert_eq!(
+            b_tree
+                .range(3..=7)
+                .rev()
+                .map(|kv| *kv.0)
+                .collect::<Vec<_>>(),
+            vec![7, 6, 5, 4, 3]
+        );
+    }
+
+    #[test]
+    fn split_off() {
+        let mut b_tree: BinaryTree<_, _> = (1..=10).map(|key| (key, key * 10)).collect();
+
+        let upper = b_tree.split_off(&6);
+
+        assert_eq!(
+            b_tree.keys().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            upper.keys().copied().collect::<Vec<_>>(),
+            vec![6, 7, 8, 9, 10]
+        );
+        assert_eq!(b_tree.len(), 5);
+        assert_eq!(upper.len(), 5);
+        assert_eq!(upper.get(&8), Some(&80));
+        assert!(b_tree.capacity() <= 8);
+        assert!(upper.capacity() <= 8);
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert_eq!(upper.check_invariants(), Ok(()));
+
+        let mut b_tree: BinaryTree<_, _> = [1, 3, 5, 7].into_iter().map(|key| (key, ())).collect();
+
+        let upper = b_tree.split_off(&4);
+
+        assert_eq!(b_tree.keys().copied().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(upper.keys().copied().collect::<Vec<_>>(), vec![5, 7]);
+    }
+
+    #[test]
+    fn append() {
+        let mut b_tree: BinaryTree<_, _> = [1, 3, 5].into_iter().map(|key| (key, "ours")).collect();
+        let mut other: BinaryTree<_, _> =
+            [5, 6, 7].into_iter().map(|key| (key, "theirs")).collect();
+
+        b_tree.append(&mut other);
+
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(other.is_empty());
+        assert_eq!(b_tree.len(), 5);
+        assert_eq!(
+            b_tree.into_iter().collect::<Vec<_>>(),
+            vec![
+                (1, "ours"),
+                (3, "ours"),
+                (5, "theirs"),
+                (6, "theirs"),
+                (7, "theirs")
+            ]
+        );
+
+        let mut b_tree: BinaryTree<_, _> = (10..20).map(|key| (key, ())).collect();
+        let mut other: BinaryTree<_, _> = (0..10).map(|key| (key, ())).collect();
+
+        b_tree.append(&mut other);
+
+        assert_eq!(
+            b_tree.keys().copied().collect::<Vec<_>>(),
+            (0..20).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn balances_on_insert() {
+        let mut ascending = BinaryTree::with_capacity(1);
+        let mut descending = BinaryTree::with_capacity(1);
+
+        for key in 1..=1000 {
+            ascending.insert(key, key);
+            descending.insert(1001 - key, key);
+        }
+
+        // a balanced tree of 1000 entries needs 1023 cells
+        assert!(ascending.capacity() <= 4096);
+        assert!(descending.capacity() <= 4096);
+
+        assert_eq!(ascending.len(), 1000);
+        assert_eq!(descending.len(), 1000);
+
+        for key in 1..=1000 {
+            assert_eq!(ascending.get(&key), Some(&key));
+            assert_eq!(descending.get(&key), Some(&(1001 - key)));
+        }
+
+        assert!(ascending.keys().copied().eq(1..=1000));
+        assert!(descending.keys().copied().eq(1..=1000));
+        assert_eq!(ascending.check_invariants(), Ok(()));
+        assert_eq!(descending.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn rebalance() {
+        let mut b_tree: BinaryTree<_, _> = (1..=1000).map(|key| (key, key)).collect();
+
+        for key in 11..=1000 {
+            b_tree.remove(&key);
+        }
+
+        let capacity = b_tree.capacity();
+
+        b_tree.rebalance();
+
+        assert!(b_tree.capacity() < capacity);
+        assert_eq!(b_tree.capacity(), 16);
+        assert_eq!(b_tree.len(), 10);
+        assert!(b_tree
+            .iter()
+            .map(|(k, v)| (*k, *v))
+            .eq((1..=10).map(|key| (key, key))));
+    }
+
+    #[test]
+    fn eq_ignores_layout() {
+        let a: BinaryTree<_, _> = [4, 2, 6, 1, 3].into_iter().map(|key| (key, ())).collect();
+        let b: BinaryTree<_, _> = [1, 2, 3, 4, 6].into_iter().map(|key| (key, ())).collect();
+        let c: BinaryTree<_, _> = [1, 2, 3, 4].into_iter().map(|key| (key, ())).collect();
+
+        assert_ne!(a.mem, b.mem);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn hash_ignores_layout() {
+        let a: BinaryTree<_, _> = [4, 2, 6, 1, 3].into_iter().map(|key| (key, key)).collect();
+        let b: BinaryTree<_, _> = [1, 2, 3, 4, 6].into_iter().map(|key| (key, key)).collect();
+
+        let set: std::collections::HashSet<_> = [a, b].into_iter().collect();
+
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn with_comparator() {
+        let mut b_tree = BinaryTree::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+
+        for key in [7, 4, 2, 5, 9, 8] {
+            b_tree.insert(key, key * 10);
+        }
+
+        assert!(b_tree.keys().copied().eq([9, 8, 7, 5, 4, 2]));
+        assert_eq!(b_tree.get(&5), Some(&50));
+        assert_eq!(b_tree.first_key_value(), Some((&9, &90)));
+        assert_eq!(b_tree.floor(&6), Some((&7, &70)));
+        assert!(b_tree
+            .range((Bound::Included(8), Bound::Included(4)))
+            .map(|kv| *kv.0)
+            .eq([8, 7, 5, 4]));
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let mut b_tree = BinaryTree::with_capacity(1024);
+
+        for key in [7, 4, 2, 5, 9] {
+            b_tree.insert(key, key * 10);
+        }
+
+        b_tree.shrink_to_fit();
+
+        assert!(b_tree.capacity() < 10);
+        assert_eq!(b_tree.len(), 5);
+
+        for key in [7, 4, 2, 5, 9] {
+            assert_eq!(b_tree.get(&key), Some(&(key * 10)));
+        }
+    }
+
+    #[test]
+    fn reserve() {
+        let mut b_tree = BinaryTree::with_capacity(1);
+
+        b_tree.insert(0, 0);
+        b_tree.reserve(100);
+
+        let capacity = b_tree.capacity();
+
+        for key in 1..=100 {
+            b_tree.insert(key, key);
+            assert_eq!(b_tree.capacity(), capacity);
+        }
+
+        let mut b_tree = BinaryTree::with_capacity(1);
+
+        b_tree.reserve(100);
+
+        let capacity = b_tree.capacity();
+
+        for key in (1..=100).rev() {
+            b_tree.insert(key, key);
+            assert_eq!(b_tree.capacity(), capacity);
+        }
+    }
+
+    #[test]
+    fn extend() {
+        let mut b_tree: BinaryTree<_, _> = [(5, "fünf"), (1, "eins")].into_iter().collect();
+
+        b_tree.extend(vec![(3, "drei"), (5, "FÜNF")]);
+
+        let other: BinaryTree<_, _> = [(2, "zwei"), (4, "vier")].into_iter().collect();
+
+        b_tree.extend(other.iter());
+
+        assert!(b_tree.into_iter().eq([
+            (1, "eins"),
+            (2, "zwei"),
+            (3, "drei"),
+            (4, "vier"),
+            (5, "FÜNF")
+        ]));
+    }
+
+    #[test]
+    fn retain() {
+        let mut b_tree: BinaryTree<_, _> = (1..=20).map(|key| (key, key)).collect();
+
+        let capacity = b_tree.capacity();
+
+        b_tree.retain(|key, value| {
+            *value *= 10;
+            key % 2 == 1
+        });
+
+        assert_eq!(b_tree.len(), 10);
+        assert_eq!(b_tree.capacity(), capacity);
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(b_tree
+            .into_iter()
+            .eq((1..=20).step_by(2).map(|key| (key, key * 10))));
+    }
+
+    #[test]
+    fn shrink_fixed() {
+        let fixed = || {
+            let mut b_tree = BinaryTree::with_capacity_fixed(5);
+
+            for key in [3, 1, 4, 0, 2] {
+                b_tree.insert(key, ());
+            }
+
+            b_tree
+        };
+
+        let shrinks: [fn(&mut BinaryTree<i32, ()>); 5] = [
+            |b_tree| b_tree.retain(|_, _| true),
+            |b_tree| b_tree.remove_range(10..20),
+            |b_tree| b_tree.retain_range(..),
+            |b_tree| b_tree.extract_if(|_, _| false).for_each(drop),
+            |b_tree| drop(b_tree.extract_if(|_, _| true)),
+        ];
+
+        for shrink in shrinks {
+            let mut b_tree = fixed();
+            shrink(&mut b_tree);
+
+            assert_eq!(b_tree.len(), 5);
+            assert_eq!(b_tree.capacity(), 5);
+            assert_eq!(b_tree.check_invariants(), Ok(()));
+            assert!(b_tree.keys().copied().eq(0..5));
+        }
+
+        let mut b_tree = fixed();
+        b_tree.remove_range(1..=1);
+
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(b_tree.keys().copied().eq([0, 2, 3, 4]));
+
+        let mut b_tree = fixed();
+        let gerade: Vec<_> = b_tree.extract_if(|key, _| key % 2 == 0).collect();
+
+        assert_eq!(gerade, [(0, ()), (2, ()), (4, ())]);
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(b_tree.keys().copied().eq([1, 3]));
+    }
+
+    #[test]
+    fn drain() {
+        let mut b_tree: BinaryTree<_, _> = (1..=10).map(|key| (key, key)).collect();
+
+        let capacity = b_tree.capacity();
+
+        let mut drain = b_tree.drain();
+
+        assert_eq!(drain.len(), 10);
+        assert_eq!(drain.next(), Some((1, 1)));
+        assert_eq!(drain.next_back(), Some((10, 10)));
+
+        drop(drain);
+
+        assert_eq!(b_tree.len(), 0);
+        assert_eq!(b_tree.capacity(), capacity);
+        assert_eq!(b_tree.iter().count(), 0);
+        assert_eq!(b_tree.get(&5), None);
+
+        b_tree.insert(3, 3);
+
+        assert!(b_tree.drain().eq([(3, 3)]));
+        assert!(b_tree.is_empty());
+
+        b_tree.extend([(1, 1), (2, 2)]);
+        core::mem::forget(b_tree.drain());
+
+        assert!(b_tree.is_empty());
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn nth() {
+        let b_tree: BinaryTree<_, _> = [13, 4, 27, 1, 9, 30]
+            .into_iter()
+            .map(|key| (key, ()))
+            .collect();
+
+        assert_eq!(b_tree.nth(0), b_tree.first_key_value());
+        assert_eq!(b_tree.nth(2), Some((&9, &())));
+        assert_eq!(b_tree.nth(b_tree.len() - 1), b_tree.last_key_value());
+        assert_eq!(b_tree.nth(b_tree.len()), None);
+
+        let mut sparse: BinaryTree<_, _> = (0..1000).map(|key| (key * 7 % 1000, key)).collect();
+        sparse.retain(|key, _| key % 50 == 0);
+        sparse.insert(3, 3);
+
+        for k in 0..=sparse.len() {
+            assert_eq!(sparse.nth(k), sparse.iter().nth(k));
+        }
+    }
+
+    #[test]
+    fn rank() {
+        let b_tree: BinaryTree<_, _> = [13, 4, 27, 1, 9, 30]
+            .into_iter()
+            .map(|key| (key, ()))
+            .collect();
+
+        assert_eq!(b_tree.rank(&1), 0);
+        assert_eq!(b_tree.rank(&0), 0);
+        assert_eq!(b_tree.rank(&9), 2);
+        assert_eq!(b_tree.rank(&10), 3);
+        assert_eq!(b_tree.rank(&99), b_tree.len());
+    }
+
+    #[test]
+    fn height() {
+        assert_eq!(BinaryTree::<i32, ()>::new().height(), 0);
+
+        let items: Vec<_> = (1..=7).map(|key| (key, ())).collect();
+        let balanced = BinaryTree::from_sorted_slice(&items);
+
+        assert_eq!(balanced.height(), 3);
+        assert_eq!(right_spine(7).height(), 7);
+    }
+
+    #[test]
+    fn depth_of() {
+        let items: Vec<_> = (1..=7).map(|key| (key, ())).collect();
+        let b_tree = BinaryTree::from_sorted_slice(&items);
+
+        assert_eq!(b_tree.depth_of(&4), Some(0));
+        assert_eq!(b_tree.depth_of(&2), Some(1));
+        assert_eq!(b_tree.depth_of(&7), Some(2));
+        assert_eq!(b_tree.depth_of(&8), None);
+    }
+
+    #[test]
+    fn is_balanced() {
+        let items: Vec<_> = (0..100).map(|key| (key, ())).collect();
+
+        assert!(BinaryTree::<i32, ()>::new().is_balanced());
+        assert!(BinaryTree::from_sorted_slice(&items).is_balanced());
+        assert!(!right_spine(3).is_balanced());
+    }
+
+    #[test]
+    fn to_dot() {
+        let b_tree = BinaryTree::from_sorted_slice(&[("eins", ()), ("vier", ()), ("zwei", ())]);
+        let dot = b_tree.to_dot();
+
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains("n0 [label=\"\\\"vier\\\"\"];"));
+        assert!(dot.contains("[label=\"\\\"eins\\\"\"];"));
+        assert!(dot.contains("[label=\"\\\"zwei\\\"\"];"));
+        assert_eq!(dot.matches("->").count(), 2);
+    }
+
+    #[test]
+    fn to_vec() {
+        let b_tree: BinaryTree<_, _> = [(3, "drei"), (1, "eins"), (2, "zwei")]
+            .into_iter()
+            .collect();
+        let cloned = b_tree.to_vec();
+
+        assert_eq!(cloned, vec![(1, "eins"), (2, "zwei"), (3, "drei")]);
+        assert_eq!(b_tree.into_sorted_vec(), cloned);
+    }
+
+    #[test]
+    fn bfs() {
+        let items: Vec<_> = (1..=7).map(|key| (key, ())).collect();
+        let b_tree = BinaryTree::from_sorted_slice(&items);
+
+        assert_eq!(
+            b_tree.bfs().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![4, 2, 6, 1, 3, 5, 7]
+        );
+        assert_eq!(
+            b_tree.bfs().rev().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![7, 5, 3, 1, 6, 2, 4]
+        );
+        assert_eq!(b_tree.bfs().len(), 7);
+
+        let spine = right_spine(3);
+
+        assert_eq!(
+            spine.bfs().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn preorder() {
+        let items: Vec<_> = (1..=7).map(|key| (key, ())).collect();
+        let b_tree = BinaryTree::from_sorted_slice(&items);
+
+        assert_eq!(
+            b_tree.preorder().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![4, 2, 1, 3, 6, 5, 7]
+        );
+        assert_eq!(b_tree.preorder().len(), 7);
+
+        let mut b_tree: BinaryTree<_, _> = [(2, ()), (1, ()), (3, ())].into_iter().collect();
+        b_tree.remove(&1);
+
+        assert_eq!(
+            b_tree.preorder().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(
+            right_spine(3)
+                .preorder()
+                .map(|(k, _)| *k)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn postorder() {
+        let items: Vec<_> = (1..=7).map(|key| (key, ())).collect();
+        let b_tree = BinaryTree::from_sorted_slice(&items);
+
+        assert_eq!(
+            b_tree.postorder().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![1, 3, 2, 5, 7, 6, 4]
+        );
+        assert_eq!(b_tree.postorder().len(), 7);
+        assert_eq!(
+            right_spine(3)
+                .postorder()
+                .map(|(k, _)| *k)
+                .collect::<Vec<_>>(),
+            vec![2, 1, 0]
+        );
+
+        let b_tree: BinaryTree<_, _> = (0..100).map(|key| (key * 7 % 100, ())).collect();
+        let root = b_tree.mem[0].as_ref().map(|(k, _)| *k);
+
+        assert_eq!(b_tree.postorder().last().map(|(k, _)| *k), root);
+    }
+
+    #[test]
+    fn index_type() {
+        let mut small: BinaryTree<u32, u32, Natural, u32> = BinaryTree::default();
+        let mut wide: BinaryTree<u32, u32> = BinaryTree::new();
+
+        for key in (0..4000).map(|key| key * 7919 % 4000) {
+            assert_eq!(small.insert(key, key), wide.insert(key, key));
+        }
+
+        for key in (0..4000).step_by(3) {
+            assert_eq!(small.remove(&key), wide.remove(&key));
+        }
+
+        assert_eq!(small.check_invariants(), Ok(()));
+
+        assert_eq!(small.len(), wide.len());
+        assert_eq!(small.capacity(), wide.capacity());
+        assert!(small.iter().eq(wide.iter()));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn index_type_size() {
+        assert!(
+            core::mem::size_of::<BinaryTree<u64, u64, Natural, u32>>()
+                < core::mem::size_of::<BinaryTree<u64, u64>>()
+        );
+    }
+
+    #[test]
+    fn index_type_children() {
+        assert_eq!(BiTree::right(u32::MAX / 2), None);
+        assert_eq!(BiTree::left(u32::MAX / 2 - 1), Some(u32::MAX - 2));
+        assert_eq!(
+            BiTree::right(u32::MAX as usize / 2),
+            Some(u32::MAX as usize + 1)
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[should_panic(expected = "value exceeds the index type")]
+    fn index_type_capacity() {
+        let capacity = u32::MAX as usize + 2;
+
+        BinaryTree::<u8, (), Natural, u32>::with_capacity_and_comparator(capacity, Natural);
+    }
+
+    #[test]
+    fn pop_min_max() {
+        let mut b_tree: BinaryTree<_, _> = (0..100).map(|key| (key * 37 % 100, ())).collect();
+
+        assert_eq!(b_tree.pop_max(), Some((99, ())));
+        assert_eq!(b_tree.len(), 99);
+
+        let mut popped = Vec::new();
+
+        while let Some((key, ())) = b_tree.pop_min() {
+            popped.push(key);
+
+            assert_eq!(b_tree.check_invariants(), Ok(()));
+        }
+
+        assert_eq!(popped, (0..99).collect::<Vec<_>>());
+        assert!(b_tree.is_empty());
+        assert_eq!(b_tree.pop_max(), None);
+    }
+
+    #[test]
+    fn get_or_insert_with() {
+        let mut b_tree: BinaryTree<_, _> = [(1, "eins")].into_iter().collect();
+
+        assert_eq!(*b_tree.get_or_insert_with(1, || unreachable!()), "eins");
+
+        *b_tree.get_or_insert_with(2, || "zwei") = "deux";
+
+        assert_eq!(b_tree.get(&2), Some(&"deux"));
+        assert_eq!(b_tree.len(), 2);
+    }
+
+    #[test]
+    fn update() {
+        let mut b_tree: BinaryTree<_, _> = [(1, 10)].into_iter().collect();
+
+        assert!(b_tree.update(&1, |value| *value += 1));
+        assert!(!b_tree.update(&2, |_| unreachable!()));
+        assert_eq!(b_tree.get(&1), Some(&11));
+        assert_eq!(b_tree.len(), 1);
+    }
+
+    #[test]
+    fn map_values() {
+        let b_tree: BinaryTree<_, _> = [(3, "drei"), (1, "eins"), (4, "vier"), (5, "fünf")]
+            .into_iter()
+            .collect();
+        let lengths = b_tree.map_values(|value| value.len());
+
+        assert_eq!(lengths.to_vec(), vec![(1, 4), (3, 4), (4, 4), (5, 5)]);
+        assert_eq!(lengths.capacity(), b_tree.capacity());
+        assert!(b_tree.bfs().map(|kv| kv.0).eq(lengths.bfs().map(|kv| kv.0)));
+    }
+
+    #[test]
+    fn borrowed_lookup() {
+        let mut b_tree: BinaryTree<String, i32> = ["eins", "zwei", "drei"]
+            .into_iter()
+            .zip(1..)
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+
+        assert_eq!(b_tree.get("zwei"), Some(&2));
+        assert_eq!(
+            b_tree.get_key_value("drei"),
+            Some((&"drei".to_string(), &3))
+        );
+        assert!(b_tree.contains_key("eins"));
+        assert!(!b_tree.contains_key("vier"));
+
+        *b_tree.get_mut("eins").unwrap() += 10;
+
+        assert_eq!(b_tree.remove("eins"), Some(11));
+        assert_eq!(b_tree.len(), 2);
+    }
+
+    #[test]
+    fn grow_rebalances() {
+        let mut b_tree = BinaryTree::new();
+
+        for key in 0..64 {
+            b_tree.insert(key, ());
+        }
+
+        assert!(b_tree.capacity() <= 128);
+
+        let mut b_tree = right_spine(3);
+
+        b_tree.insert(3, ());
+
+        assert_eq!(b_tree.capacity(), 8);
+        assert!(b_tree.is_balanced());
+        assert!(b_tree.keys().copied().eq(0..4));
+    }
+
+    #[test]
+    fn fixed_capacity() {
+        let mut b_tree = BinaryTree::with_capacity_fixed(8);
+        let mut inserted = 0;
+
+        let err = loop {
+            match b_tree.try_insert_no_grow(inserted, ()) {
+                Ok(None) => inserted += 1,
+                Ok(Some(())) => unreachable!(),
+                Err(err) => break err,
+            }
+        };
+
+        assert_eq!(err.key, inserted);
+        assert_eq!(b_tree.capacity(), 8);
+        assert_eq!(b_tree.len(), inserted);
+        assert!(b_tree.keys().copied().eq(0..inserted));
+        assert_eq!(b_tree.try_insert_no_grow(0, ()).unwrap(), Some(()));
+
+        b_tree.reserve(100);
+
+        assert_eq!(b_tree.capacity(), 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "the fixed capacity of the tree is exhausted")]
+    fn fixed_capacity_insert_panics() {
+        let mut b_tree = BinaryTree::with_capacity_fixed(4);
+
+        for key in 0..4 {
+            b_tree.insert(key, ());
+        }
+    }
+
+    #[test]
+    fn fixed_capacity_rebalance() {
+        let mut b_tree = BinaryTree::with_capacity_fixed(1024);
+
+        for key in 0..3 {
+            b_tree.insert(key, ());
+        }
+
+        b_tree.rebalance();
+
+        assert_eq!(b_tree.capacity(), 1024);
+        assert_eq!(b_tree.height(), 2);
+        assert!(b_tree.keys().copied().eq(0..3));
+    }
+
+    #[test]
+    fn fixed_capacity_split_off() {
+        let mut b_tree = BinaryTree::with_capacity_fixed(64);
+
+        for key in 0..6 {
+            b_tree.insert(key, ());
+        }
+
+        let upper = b_tree.split_off(&3);
+
+        assert_eq!(b_tree.capacity(), 64);
+        assert_eq!(upper.capacity(), 64);
+        assert!(b_tree.keys().copied().eq(0..3));
+        assert!(upper.keys().copied().eq(3..6));
+    }
+
+    #[test]
+    fn fixed_capacity_append() {
+        let mut b_tree = BinaryTree::with_capacity_fixed(8);
+        b_tree.insert(3, "drei");
+        b_tree.insert(1, "eins");
+
+        let mut other: BinaryTree<_, _> = (1..=6).map(|key| (key, "neu")).collect();
+        b_tree.append(&mut other);
+
+        assert_eq!(b_tree.capacity(), 8);
+        assert_eq!(b_tree.len(), 6);
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(other.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "the fixed capacity of the tree is exhausted")]
+    fn fixed_capacity_append_panics() {
+        let mut b_tree = BinaryTree::with_capacity_fixed(8);
+        let mut other: BinaryTree<_, _> = (0..90).map(|key| (key, ())).collect();
+
+        b_tree.append(&mut other);
+    }
+
+    #[test]
+    fn fixed_capacity_partition() {
+        let mut b_tree = BinaryTree::with_capacity_fixed(16);
+
+        for key in 0..4 {
+            b_tree.insert(key, ());
+        }
+
+        let (even, odd) = b_tree.partition(|key, _| key % 2 == 0);
+
+        assert_eq!(even.capacity(), 16);
+        assert_eq!(odd.capacity(), 16);
+        assert!(even.keys().copied().eq([0, 2]));
+        assert!(odd.keys().copied().eq([1, 3]));
+    }
+
+    #[test]
+    fn into_iter_borrowed() {
+        let mut b_tree: BinaryTree<_, _> = [(2, 20), (1, 10), (3, 30)].into_iter().collect();
+
+        for (_, value) in &mut b_tree {
+            *value += 1;
+        }
+
+        let mut keys = Vec::new();
+
+        for (key, value) in &b_tree {
+            assert_eq!(*value, key * 10 + 1);
+            keys.push(*key);
+        }
+
+        assert_eq!(keys, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn bitree_macro() {
+        let b_tree = bitree! { 7 => "sieben", 4 => "vier", 9 => "neun", };
+
+        assert_eq!(
+            b_tree.into_iter().collect::<Vec<_>>(),
+            vec![(4, "vier"), (7, "sieben"), (9, "neun")]
+        );
+
+        let empty: BinaryTree<i32, &str> = bitree! {};
+
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn index() {
+        let b_tree: BinaryTree<_, _> = [(1, "eins"), (2, "zwei")].into_iter().collect();
+
+        assert_eq!(b_tree[&2], "zwei");
+    }
+
+    #[test]
+    #[should_panic(expected = "no entry found for key")]
+    fn index_missing() {
+        let b_tree: BinaryTree<_, _> = [(1, "eins")].into_iter().collect();
+
+        let _ = b_tree[&3];
+    }
+
+    #[test]
+    fn from_array() {
+        let b_tree = BinaryTree::from([(3, "c"), (1, "a"), (2, "b")]);
+
+        assert_eq!(
+            b_tree.into_iter().collect::<Vec<_>>(),
+            vec![(1, "a"), (2, "b"), (3, "c")]
+        );
+    }
+
+    #[test]
+    fn try_from_vec() {
+        let b_tree = BinaryTree::try_from(vec![(3, "c"), (1, "a"), (2, "b")]).unwrap();
+
+        assert!(b_tree.is_balanced());
+        assert_eq!(
+            b_tree.into_iter().collect::<Vec<_>>(),
+            vec![(1, "a"), (2, "b"), (3, "c")]
+        );
+
+        let err = BinaryTree::try_from(vec![(3, "c"), (1, "a"), (3, "d")]).unwrap_err();
+
+        assert_eq!(err.key, 3);
+    }
+
+    #[test]
+    fn insert_grows_repeatedly() {
+        let mut b_tree = BinaryTree::with_capacity(1);
+
+        for key in [1, 2, 3, 0] {
+            b_tree.insert(key, ());
+        }
+
+        assert!(b_tree.capacity() >= 4);
+        assert!(b_tree.keys().copied().eq(0..4));
+    }
+
+    #[test]
+    fn shrink_to() {
+        let mut b_tree = BinaryTree::with_capacity(1024);
+
+        for key in [2, 3, 1] {
+            b_tree.insert(key, key * 10);
+        }
+
+        b_tree.shrink_to(16);
+
+        assert_eq!(b_tree.capacity(), 16);
+        assert!(b_tree.iter().eq([(&1, &10), (&2, &20), (&3, &30)]));
+
+        b_tree.shrink_to(0);
+
+        assert_eq!(b_tree.capacity(), 4);
+        assert_eq!(b_tree.len(), 3);
+
+        b_tree.shrink_to(64);
+
+        assert_eq!(b_tree.capacity(), 4);
+    }
+
+    #[test]
+    fn is_complete_and_perfect() {
+        let mut b_tree: BinaryTree<_, _> = [4, 2, 6, 1, 3, 5, 7]
+            .into_iter()
+            .map(|key| (key, ()))
+            .collect();
+
+        assert!(b_tree.is_complete());
+        assert!(b_tree.is_perfect());
+
+        b_tree.remove(&7);
+
+        assert!(b_tree.is_complete());
+        assert!(!b_tree.is_perfect());
+
+        b_tree.remove(&1);
+
+        assert!(!b_tree.is_complete());
+        assert!(!right_spine(2).is_complete());
+        assert!(BinaryTree::<i32, ()>::new().is_perfect());
+    }
+
+    #[test]
+    fn count_leaves_and_internal() {
+        let mut balanced: BinaryTree<_, _> = (1..=7).map(|key| (key, ())).collect();
+        balanced.rebalance();
+
+        assert_eq!(balanced.count_leaves(), 4);
+        assert_eq!(balanced.count_internal(), 3);
+        assert_eq!(right_spine(5).count_leaves(), 1);
+
+        let mut b_tree: BinaryTree<_, _> = (0..100).map(|key| (key * 37 % 100, ())).collect();
+
+        for key in (0..100).step_by(7) {
+            b_tree.remove(&key);
+
+            assert_eq!(
+                b_tree.count_leaves() + b_tree.count_internal(),
+                b_tree.len()
+            );
+        }
+    }
+
+    #[test]
+    fn display() {
+        let mut b_tree = BinaryTree::new();
+
+        for key in [4, 2, 6, 1, 3, 5, 7] {
+            b_tree.insert(key, ());
+        }
+
+        assert_eq!(
+            b_tree.to_string(),
+            "4\n├── 2\n│   ├── 1\n│   └── 3\n└── 6\n    ├── 5\n    └── 7\n"
+        );
+
+        b_tree.remove(&5);
+
+        assert_eq!(
+            format!("{:#}", b_tree),
+            "4\n├── 2\n│   ├── 1\n│   └── 3\n└── 6\n    ├──\n    └── 7\n"
+        );
+        assert_eq!(BinaryTree::<i32, ()>::new().to_string(), "");
+    }
+
+    #[test]
+    fn debug() {
+        let b_tree = BinaryTree::from_sorted_slice(&[(1, "eins"), (2, "zwei"), (3, "drei")]);
+
+        assert_eq!(
+            format!("{:?}", b_tree),
+            r#"{1: "eins", 2: "zwei", 3: "drei"}"#
+        );
+
+        let mut b_tree = BinaryTree::new();
+
+        b_tree.insert(2, "zwei");
+        b_tree.insert(3, "drei");
+
+        assert_eq!(
+            format!("{:#?}", b_tree),
+            r#"{
+    0: (
+        2,
+        "zwei",
+    ),
+    2: (
+        3,
+        "drei",
+    ),
+}"#
+        );
+    }
+
+    #[test]
+    fn check_invariants() {
+        let items: Vec<_> = (1..=7).map(|key| (key, ())).collect();
+
+        assert_eq!(
+            BinaryTree::from_sorted_slice(&items).check_invariants(),
+            Ok(())
+        );
+        assert_eq!(right_spine(4).check_invariants(), Ok(()));
+
+        let mut b_tree = BinaryTree::from_sorted_slice(&items);
+        b_tree.mem.swap(3, 4);
+
+        assert_eq!(
+            b_tree.check_invariants(),
+            Err("the key at index 4 is out of order".to_string())
+        );
+
+        let mut b_tree = BinaryTree::from_sorted_slice(&items);
+        b_tree.mem[1] = None;
+        b_tree.set_len(6);
+
+        assert_eq!(
+            b_tree.check_invariants(),
+            Err("the entry at index 3 is below an empty cell".to_string())
+        );
+
+        let mut b_tree = BinaryTree::from_sorted_slice(&items);
+        b_tree.set_len(8);
+
+        assert_eq!(
+            b_tree.check_invariants(),
+            Err("len() is 8 but 7 cells are occupied".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_reachable() {
+        let mut b_tree: BinaryTree<_, _> = (0..50).map(|key| (key * 13 % 50, key)).collect();
+        let mut removed = Vec::new();
+
+        // the root always has children here, so every removal fills a hole
+        for _ in 0..10 {
+            let key = b_tree.mem[0].as_ref().unwrap().0;
+
+            assert!(b_tree.occupied_left(0).is_some() || b_tree.occupied_right(0).is_some());
+
+            b_tree.remove(&key);
+            removed.push(key);
+
+            assert!(b_tree.validate_reachable());
+        }
+
+        for key in (0..50).filter(|key| !removed.contains(key)) {
+            assert!(b_tree.get(&key).is_some());
+        }
+
+        let mut b_tree = BinaryTree::from_sorted_slice(&[(1, 'a'), (2, 'b'), (3, 'c')]);
+        b_tree.mem[0] = None;
+
+        assert!(!b_tree.validate_reachable());
+    }
+
+    #[test]
+    fn partial_ord() {
+        let one_two = BinaryTree::from([(1, ()), (2, ())]);
+        let one_three = BinaryTree::from([(1, ()), (3, ())]);
+
+        assert!(one_two < one_three);
+        assert_eq!(one_two.partial_cmp(&one_two.clone()), Some(Ordering::Equal));
+        assert!(BinaryTree::from([(1, ())]) < one_two);
+        assert!(BinaryTree::from([(1, 2)]) > BinaryTree::from([(1, 1)]));
+
+        let mut rebuilt = one_three.clone();
+        rebuilt.reserve(100);
+
+        assert_eq!(one_three.partial_cmp(&rebuilt), Some(Ordering::Equal));
+        assert_eq!(
+            BinaryTree::from([(1, f64::NAN)]).partial_cmp(&BinaryTree::from([(1, 0.0)])),
+            None
+        );
+    }
+
+    #[test]
+    fn ord() {
+        let one_two = BinaryTree::from([(1, ()), (2, ())]);
+        let one_three = BinaryTree::from([(1, ()), (3, ())]);
+
+        assert_eq!(one_two.cmp(&one_three), Ordering::Less);
+        assert_eq!(one_three.cmp(&one_two.clone()), Ordering::Greater);
+
+        let mut rebuilt = one_three.clone();
+        rebuilt.reserve(100);
+
+        assert_eq!(one_three.cmp(&rebuilt), Ordering::Equal);
+
+        let mut trees = vec![
+            one_three.clone(),
+            one_two.clone(),
+            BinaryTree::from([(1, ())]),
+        ];
+        trees.sort();
+
+        assert_eq!(
+            trees,
+            [
+                BinaryTree::from([(1, ())]),
+                one_two.clone(),
+                one_three.clone()
+            ]
+        );
+        assert_eq!(one_two.clone().max(one_three.clone()), one_three);
+        assert_eq!(one_three.first_key_value(), Some((&1, &())));
+    }
+
+    #[test]
+    fn set_operations() {
+        let eins: BinaryTree<_, _> = [1, 3, 5, 7, 9].into_iter().map(|key| (key, ())).collect();
+        let zwei: BinaryTree<_, _> = [2, 3, 4, 5, 10].into_iter().map(|key| (key, ())).collect();
+
+        assert!(eins.intersection(&zwei).copied().eq([3, 5]));
+        assert!(eins.union(&zwei).copied().eq([1, 2, 3, 4, 5, 7, 9, 10]));
+        assert!(eins.difference(&zwei).copied().eq([1, 7, 9]));
+        assert!(zwei.difference(&eins).copied().eq([2, 4, 10]));
+    }
+
+    #[test]
+    fn set_operations_disjoint() {
+        let low: BinaryTree<_, _> = (0..5).map(|key| (key, ())).collect();
+        let high: BinaryTree<_, _> = (5..10).map(|key| (key, ())).collect();
+        let empty = BinaryTree::new();
+
+        assert_eq!(low.intersection(&high).count(), 0);
+        assert!(high.union(&low).copied().eq(0..10));
+        assert!(low.difference(&high).copied().eq(0..5));
+        assert!(low.union(&empty).copied().eq(0..5));
+        assert_eq!(empty.difference(&low).count(), 0);
+    }
+
+    #[test]
+    fn subset_superset_disjoint() {
+        let klein: BinaryTree<_, _> = [2, 4].into_iter().map(|key| (key, ())).collect();
+        let gross: BinaryTree<_, _> = (1..=5).map(|key| (key, ())).collect();
+        let fremd: BinaryTree<_, _> = (6..=8).map(|key| (key, ())).collect();
+        let empty = BinaryTree::new();
+
+        assert!(klein.is_subset(&gross));
+        assert!(!gross.is_subset(&klein));
+        assert!(gross.is_superset(&klein));
+        assert!(!klein.is_superset(&gross));
+        assert!(!klein.is_disjoint(&gross));
+
+        assert!(gross.is_subset(&gross.clone()));
+        assert!(gross.is_superset(&gross.clone()));
+
+        assert!(gross.is_disjoint(&fremd));
+        assert!(!fremd.is_subset(&gross));
+
+        assert!(empty.is_subset(&klein));
+        assert!(klein.is_superset(&empty));
+        assert!(empty.is_disjoint(&empty));
+        assert!(!klein.is_subset(&empty));
+    }
+
+    #[test]
+    fn remove_range() {
+        let mut b_tree: BinaryTree<_, _> = (0..10).map(|key| (key, key * 10)).collect();
+
+        b_tree.remove_range(3..7);
+
+        assert_eq!(b_tree.len(), 6);
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(b_tree.keys().copied().eq([0, 1, 2, 7, 8, 9]));
+        assert_eq!(b_tree.get(&7), Some(&70));
+
+        b_tree.remove_range(..=1);
+        b_tree.remove_range(9..);
+
+        assert_eq!(
+            b_tree.into_iter().collect::<Vec<_>>(),
+            vec![(2, 20), (7, 70), (8, 80)]
+        );
+    }
+
+    #[test]
+    fn get_disjoint_mut() {
+        let mut b_tree: BinaryTree<_, _> = (1..=7).map(|key| (key, key * 10)).collect();
+
+        let (vier, sieben) = b_tree.get_disjoint_mut(&4, &7).unwrap();
+        core::mem::swap(vier, sieben);
+
+        let (zwei, eins) = b_tree.get_disjoint_mut(&2, &1).unwrap();
+        core::mem::swap(zwei, eins);
+
+        assert_eq!(b_tree.get(&4), Some(&70));
+        assert_eq!(b_tree.get(&7), Some(&40));
+        assert_eq!(b_tree.get(&1), Some(&20));
+        assert_eq!(b_tree.get(&2), Some(&10));
+
+        assert!(b_tree.get_disjoint_mut(&3, &3).is_none());
+        assert!(b_tree.get_disjoint_mut(&3, &8).is_none());
+        assert!(b_tree.get_disjoint_mut(&0, &3).is_none());
+    }
+
+    #[test]
+    fn contains_value() {
+        let b_tree: BinaryTree<_, _> = [(1, "eins"), (2, "zwei"), (3, "drei")].into();
 
-impl<K, V> BinaryTree<K, V> {
-    pub fn new() -> Self {
-        Self::with_capacity(8)
+        assert!(b_tree.contains_value(&"zwei"));
+        assert!(!b_tree.contains_value(&"vier"));
+        assert!(!BinaryTree::<i32, &str>::new().contains_value(&"eins"));
     }
 
-    pub fn with_capacity(mut capacity: usize) -> Self {
-        capacity = capacity.max(1);
+    #[test]
+    fn count_matching() {
+        let b_tree: BinaryTree<_, _> =
+            [(1, "eins"), (2, "zwei"), (3, "drei"), (7, "sieben")].into();
 
-        Self(
-            std::iter::repeat_with(Default::default)
-                .take(capacity)
-                .collect(),
-        )
+        assert_eq!(b_tree.count_matching(|_, value| value.len() > 4), 1);
+        assert_eq!(b_tree.count_matching(|_, value| value.len() > 3), 4);
+        assert_eq!(b_tree.count_matching(|key, _| key % 2 == 1), 3);
     }
 
-    pub fn capacity(&self) -> usize {
-        self.0.len()
+    #[test]
+    fn partition() {
+        let b_tree: BinaryTree<_, _> = (0..20).map(|key| (key, key * 10)).collect();
+
+        let (gerade, ungerade) = b_tree.partition(|key, _| key % 2 == 0);
+
+        assert_eq!(gerade.check_invariants(), Ok(()));
+        assert_eq!(ungerade.check_invariants(), Ok(()));
+        assert!(gerade.is_balanced() && ungerade.is_balanced());
+        assert!(gerade.keys().copied().eq((0..20).step_by(2)));
+        assert!(ungerade
+            .into_iter()
+            .eq((1..20).step_by(2).map(|key| (key, key * 10))));
     }
-}
 
-impl<K: Debug, V: Debug> Debug for BinaryTree<K, V> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let alternate = f.alternate();
+    #[test]
+    fn btree_map_round_trip() {
+        let map: BTreeMap<_, _> = (0..50).map(|key| (key, key.to_string())).collect();
 
-        let mut map = f.debug_map();
+        let b_tree = BinaryTree::from(map.clone());
 
-        for entry in self.0.iter() {
-            if let Some((key, value)) = entry {
-                map.entry(key, value);
-            } else if alternate {
-                map.entry(&Option::<K>::default(), &Option::<V>::default());
-            }
-        }
+        assert_eq!(b_tree.len(), 50);
+        assert!(b_tree.is_balanced());
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert_eq!(BTreeMap::from(b_tree), map);
+    }
 
-        map.finish()
+    #[test]
+    fn iter_mut_send_sync() {
+        fn send_sync<T: Send + Sync>(_: &T) {}
+
+        let mut b_tree: BinaryTree<_, _> = (0..10).map(|key| (key, key)).collect();
+
+        send_sync(&b_tree.iter_mut());
+        send_sync(&b_tree.values_mut());
+
+        let values = b_tree.values_mut();
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| values.for_each(|value| *value *= 10));
+        });
+
+        assert!(b_tree.values().copied().eq((0..100).step_by(10)));
     }
-}
 
-///   
-///                 E0
-///         +-------+-------+
-///         E1              E2
-///     +---+---+       +---+---+
-///     E3      ()      E5      E6
-///   +-+-+   +-+-+   +-+-+   +-+-+
-///   ()  E8  ()  ()  E11 E12 ()  E14
-///
-/// +----+----+----+----+----+----+----+----+----+----+----+-----+-----+----+-----+
-/// | E0 | E1 | E2 | E3 | () | E5 | E6 | () | E8 | () | () | E11 | E12 | () | E14 |
-/// +----+----+----+----+----+----+----+----+----+----+----+-----+-----+----+-----+
-impl<K, V> BinaryTree<K, V>
-where
-    K: Ord,
-{
-    fn find_index(&self, key: &K) -> usize {
-        let Self(mem) = self;
+    #[test]
+    fn get_fast() {
+        let b_tree: BinaryTree<_, _> = (0..100).map(|key| (key * 2, key)).collect();
 
-        let mut index = 0;
+        for key in -1..201 {
+            assert_eq!(b_tree.get_fast(&key), b_tree.get(&key));
+        }
 
-        while let Some(
-            // the location exists
-            Some(
-                // and there is something
-                (r_key, _),
-            ),
-        ) = mem.get(index)
-        {
-            if r_key == key {
-                // Found Entry
-                break;
-            } else {
-                // Walk further
+        let spine = right_spine(6);
 
-                index = if key < r_key {
-                    BiTree::left(index)
-                } else {
-                    BiTree::right(index)
-                }
-            }
+        for key in 0..8 {
+            assert_eq!(spine.get_fast(&key), spine.get(&key));
         }
 
-        index
-    }
+        let mut b_tree = BinaryTree::with_capacity(5);
 
-    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        let index = self.find_index(&key);
+        for key in [3, 1, 4, 0, 2, 9, 6] {
+            b_tree.insert(key, key);
+        }
 
-        if let Some(cell) = self.0.get_mut(index) {
-            let result = cell.replace((key, value)).map(|kv| kv.1);
+        b_tree.remove(&3);
+        b_tree.remove(&6);
 
-            // TODO: check balance
+        for key in -1..11 {
+            assert_eq!(b_tree.get_fast(&key), b_tree.get(&key));
+        }
+    }
 
-            result
-        } else {
-            self.grow();
+    #[test]
+    fn remove_and_maybe_shrink() {
+        let mut b_tree: BinaryTree<_, _> = (0..1000).map(|key| (key, key)).collect();
+        let capacity = b_tree.capacity();
 
-            self.insert(key, value)
+        for key in 0..990 {
+            assert_eq!(b_tree.remove_and_maybe_shrink(&key), Some(key));
+            assert_eq!(b_tree.check_invariants(), Ok(()));
         }
+
+        assert!(b_tree.capacity() < capacity / 16);
+        assert!(b_tree.keys().copied().eq(990..1000));
+        assert_eq!(b_tree.remove_and_maybe_shrink(&0), None);
     }
 
-    fn grow(&mut self) {
-        let new_capacity = self.capacity() * 2;
+    #[test]
+    fn navigable_views() {
+        let b_tree: BinaryTree<_, _> = (0..10).map(|key| (key, ())).collect();
 
-        self.0 = self
-            .0
-            .iter_mut() // We have to do iter_mut to move everything
-            .map(Option::take) // We move out of old_inner
-            .chain(std::iter::repeat_with(Default::default))
-            .take(new_capacity)
-            .collect();
+        assert!(b_tree.headmap(&5, false).map(|kv| *kv.0).eq(0..5));
+        assert!(b_tree.headmap(&5, true).map(|kv| *kv.0).eq(0..=5));
+        assert!(b_tree.tailmap(&7, false).map(|kv| *kv.0).eq(8..10));
+        assert!(b_tree.tailmap(&7, true).map(|kv| *kv.0).eq(7..10));
+        assert!(b_tree.submap(&2, true, &6, false).map(|kv| *kv.0).eq(2..6));
+        assert!(b_tree.submap(&2, false, &6, true).map(|kv| *kv.0).eq(3..=6));
+        assert_eq!(b_tree.headmap(&0, false).count(), 0);
     }
 
-    pub fn get(&self, key: &K) -> Option<&V> {
-        let index = self.find_index(key);
+    #[test]
+    fn into_keys_values() {
+        let b_tree: BinaryTree<_, _> = [(3, "drei"), (1, "eins"), (2, "zwei")].into();
 
-        if let Some(cell) = self.0.get(index) {
-            cell.as_ref().map(|kv| &kv.1)
-        } else {
-            None
-        }
+        let keys: Vec<_> = b_tree.clone().into_keys().collect();
+
+        assert_eq!(keys, vec![1, 2, 3]);
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(b_tree.into_values().rev().eq(["drei", "zwei", "eins"]));
     }
-}
 
-mod BiTree {
-    pub fn is_right(index: usize) -> bool {
-        index % 2 == 0
+    #[test]
+    fn replace() {
+        let mut b_tree: BinaryTree<_, _> = [(1, "eins"), (2, "zwei")].into();
+
+        assert_eq!(b_tree.replace(&2, "two"), Some("zwei"));
+        assert_eq!(b_tree.get(&2), Some(&"two"));
+
+        assert_eq!(b_tree.replace(&3, "three"), None);
+        assert!(!b_tree.contains_key(&3));
+        assert_eq!(b_tree.len(), 2);
     }
 
-    pub fn parrent(index: usize) -> Option<usize> {
-        if index > 0 {
-            Some(if is_right(index) {
-                (index - 2) / 2
-            } else {
-                (index - 1) / 2
-            })
-        } else {
-            None
+    #[test]
+    fn drop_large() {
+        use std::rc::Rc;
+
+        let shared = Rc::new(());
+
+        // Ascending inserts, so the tree is as deep as growth allows.
+        let mut b_tree = BinaryTree::new();
+
+        for key in 0..100_000 {
+            b_tree.insert(key, Rc::clone(&shared));
         }
+
+        assert_eq!(Rc::strong_count(&shared), 100_001);
+
+        drop(b_tree);
+
+        assert_eq!(Rc::strong_count(&shared), 1);
     }
 
-    pub fn right(index: usize) -> usize {
-        index * 2 + 2
+    #[test]
+    fn bulk_insert_sorted() {
+        let mut b_tree: BinaryTree<_, _> = (1..=3).map(|key| (key, 0)).collect();
+
+        b_tree.bulk_insert_sorted([(3, 30), (8, 80), (9, 90), (10, 100)]);
+
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(b_tree.is_balanced());
+        assert_eq!(
+            b_tree.into_iter().collect::<Vec<_>>(),
+            vec![(1, 0), (2, 0), (3, 30), (8, 80), (9, 90), (10, 100)]
+        );
     }
 
-    pub fn left(index: usize) -> usize {
-        index * 2 + 1
+    #[test]
+    fn bulk_insert_sorted_fixed() {
+        let mut b_tree = BinaryTree::with_capacity_fixed(8);
+        b_tree.insert(2, 0);
+
+        b_tree.bulk_insert_sorted((1..=5).map(|key| (key, key)));
+
+        assert_eq!(b_tree.capacity(), 8);
+        assert!(b_tree.values().copied().eq(1..=5));
     }
-}
 
-impl<K, V> IntoIterator for BinaryTree<K, V> {
-    type Item = (K, V);
-    type IntoIter = BinaryTreeIter<K, V>;
+    #[test]
+    #[should_panic(expected = "the fixed capacity of the tree is exhausted")]
+    fn bulk_insert_sorted_fixed_panics() {
+        let mut b_tree = BinaryTree::with_capacity_fixed(8);
 
-    fn into_iter(self) -> Self::IntoIter {
-        Self::IntoIter {
-            indexer: BiTreeIndexIter::new(self.capacity()),
-            tree: self,
-        }
+        b_tree.bulk_insert_sorted((0..40).map(|key| (key, ())));
     }
-}
 
-///
-/// Iterates over a BinaryTree in order.
-///
-pub struct BinaryTreeIter<K, V> {
-    tree: BinaryTree<K, V>,
-    indexer: BiTreeIndexIter,
-}
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "bulk_insert_sorted requires sorted, unique keys")]
+    fn bulk_insert_unsorted() {
+        BinaryTree::new().bulk_insert_sorted([(2, ()), (1, ())]);
+    }
 
-impl<K, V> Iterator for BinaryTreeIter<K, V> {
-    type Item = (K, V);
+    #[test]
+    fn retain_range() {
+        let mut b_tree: BinaryTree<_, _> = (0..10).map(|key| (key, key * 10)).collect();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        while let Some(index) = self.indexer.next() {
-            // SAFETY: BiTreeIndexIter is limited to the capacity of tree.0
-            if let Some(res) = unsafe { self.tree.0.get_unchecked_mut(index) }.take() {
-                return Some(res);
-            }
-        }
-        None
+        b_tree.retain_range(3..=7);
+
+        assert_eq!(b_tree.len(), 5);
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(b_tree.keys().copied().eq(3..=7));
+
+        b_tree.retain_range(..5);
+
+        assert_eq!(
+            b_tree.into_iter().collect::<Vec<_>>(),
+            vec![(3, 30), (4, 40)]
+        );
     }
-}
 
-struct BiTreeIndexIter {
-    capacity: usize,
-    stack: Vec<usize>,
-    current: Option<usize>,
-}
+    #[test]
+    fn get_many() {
+        let b_tree: BinaryTree<_, _> = (0..20).map(|key| (key * 2, key)).collect();
 
-impl BiTreeIndexIter {
-    fn new(capacity: usize) -> Self {
-        Self {
-            capacity,
-            stack: Vec::new(),
-            current: Some(0),
+        assert_eq!(
+            b_tree.get_many(&[10, 3, 0, 38, 40, 10]),
+            vec![Some(&5), None, Some(&0), Some(&19), None, Some(&5)]
+        );
+        assert!(b_tree.get_many(&[]).is_empty());
+
+        let mut b_tree: BinaryTree<_, _> = (0..100).map(|key| (key * 37 % 100, key)).collect();
+
+        for key in (0..100).step_by(3) {
+            b_tree.remove(&key);
         }
+
+        let keys: Vec<_> = (0..300).map(|key| key * 53 % 110).collect();
+        let expected: Vec<_> = keys.iter().map(|key| b_tree.get(key)).collect();
+
+        assert_eq!(b_tree.get_many(&keys), expected);
     }
 
-    fn left(&self, node: usize) -> Option<usize> {
-        let index = BiTree::left(node);
+    #[test]
+    fn merge_with() {
+        let mut b_tree = BinaryTree::with_capacity(1);
 
-        if index < self.capacity {
-            Some(index)
-        } else {
-            None
+        for word in "a b a c a b".split(' ') {
+            b_tree.merge_with(word, 1, |old, new| old + new);
         }
+
+        assert_eq!(
+            b_tree.into_iter().collect::<Vec<_>>(),
+            vec![("a", 3), ("b", 2), ("c", 1)]
+        );
     }
 
-    fn right(&self, node: usize) -> Option<usize> {
-        let index = BiTree::right(node);
+    #[test]
+    fn try_reserve() {
+        let mut b_tree: BinaryTree<_, _> = (0..10).map(|key| (key, key)).collect();
 
-        if index < self.capacity {
-            Some(index)
-        } else {
-            None
+        assert!(b_tree.try_reserve(100).is_ok());
+        assert!(b_tree.capacity() >= 110);
+
+        let capacity = b_tree.capacity();
+
+        for key in 10..110 {
+            b_tree.insert(key, key);
         }
+
+        assert_eq!(b_tree.capacity(), capacity);
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+
+        let error: Result<(), TryReserveError> = b_tree.try_reserve(usize::MAX);
+
+        assert!(error.is_err());
+        assert_eq!(b_tree.len(), 110);
     }
-}
 
-impl Iterator for BiTreeIndexIter {
-    type Item = usize;
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            if let Some(node) = self.current {
-                self.stack.push(node);
-                self.current = self.left(node);
-            } else if let Some(node) = self.stack.pop() {
-                self.current = self.right(node);
-                return Some(node);
-            } else {
-                return None;
-            }
-        }
+    #[test]
+    fn extract_if() {
+        let mut b_tree: BinaryTree<_, _> = (0..20).map(|key| (key, key * 10)).collect();
+
+        let ungerade: Vec<_> = b_tree.extract_if(|key, _| key % 2 == 1).collect();
+
+        assert!(ungerade
+            .into_iter()
+            .eq((1..20).step_by(2).map(|key| (key, key * 10))));
+        assert_eq!(b_tree.len(), 10);
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(b_tree.is_balanced());
+        assert!(b_tree.keys().copied().eq((0..20).step_by(2)));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn extract_if_dropped_early() {
+        let mut b_tree: BinaryTree<_, _> = (0..10).map(|key| (key, ())).collect();
+
+        assert_eq!(b_tree.extract_if(|key, _| *key >= 3).next(), Some((3, ())));
+
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+        assert!(b_tree.keys().copied().eq([0, 1, 2, 4, 5, 6, 7, 8, 9]));
+    }
 
     #[test]
-    fn it_works() {
-        let mut b_tree = BinaryTree::with_capacity(8);
+    fn count_range() {
+        let b_tree: BinaryTree<_, _> = (0..50).map(|key| (key * 2, ())).collect();
 
-        b_tree.insert(7, "sieben");
-        b_tree.insert(4, "vier");
-        b_tree.insert(2, "zwei");
-        b_tree.insert(5, "fünf");
+        assert_eq!(b_tree.count_range(..), b_tree.len());
+        assert_eq!(b_tree.count_range(10..20), 5);
+        assert_eq!(b_tree.count_range(11..=21), 5);
+        assert_eq!(b_tree.count_range(..0), 0);
+        assert_eq!(b_tree.count_range(90..), 5);
+    }
 
-        assert_eq!(b_tree.get(&2), Some("zwei").as_ref());
-        assert_eq!(b_tree.get(&5), Some("fünf").as_ref());
+    #[test]
+    fn min_max_by_value() {
+        let b_tree: BinaryTree<_, _> = [
+            (1, "eins"),
+            (2, "zwei"),
+            (3, "drei"),
+            (7, "sieben"),
+            (9, "neun"),
+        ]
+        .into();
+
+        assert_eq!(
+            b_tree.max_by(|a, b| a.len().cmp(&b.len())),
+            Some((&7, &"sieben"))
+        );
+        assert_eq!(
+            b_tree.min_by(|a, b| a.len().cmp(&b.len())),
+            Some((&1, &"eins"))
+        );
+        assert_eq!(b_tree.min_by(|a, b| a.cmp(b)), Some((&3, &"drei")));
+        assert_eq!(BinaryTree::<i32, &str>::new().max_by(|a, b| a.cmp(b)), None);
     }
 
     #[test]
-    fn balancing(){
+    fn bi_tree_overflow() {
+        let last_left = (usize::MAX - 1) / 2;
+
+        assert_eq!(BiTree::left(last_left), Some(usize::MAX));
+        assert_eq!(BiTree::right(last_left), None);
+        assert_eq!(BiTree::left(last_left + 1), None);
+        assert_eq!(BiTree::right(last_left - 1), Some(usize::MAX - 1));
+        assert_eq!(BiTree::left(usize::MAX), None);
+
+        assert_eq!(BiTree::parrent(usize::MAX), Some(last_left));
+        assert_eq!(BiTree::depth(usize::MAX), usize::BITS as usize);
+        assert_eq!(BiTree::depth(usize::MAX - 1), usize::BITS as usize - 1);
+
+        assert_eq!(BiTree::left(0usize), Some(1));
+        assert_eq!(BiTree::right(0usize), Some(2));
+        assert_eq!(BiTree::depth(0), 0);
+    }
+
+    #[test]
+    fn remove_leaf() {
         let mut b_tree = BinaryTree::with_capacity(8);
 
-        b_tree.insert(1, "eins");
-        println!("{:#?}", b_tree);
-        b_tree.insert(2, "zwei");
-        println!("{:#?}", b_tree);
-        b_tree.insert(3, "drei");
-        println!("{:#?}", b_tree);
         b_tree.insert(4, "vier");
-        println!("{:#?}", b_tree);
-        b_tree.insert(5, "fünf");
-        println!("{:#?}", b_tree);
+        b_tree.insert(2, "zwei");
         b_tree.insert(6, "sechs");
-        println!("{:#?}", b_tree);
-        b_tree.insert(7, "sieben");
-        println!("{:#?}", b_tree);
 
-        
-        let vec: Vec<_> = b_tree.into_iter().map(|kv| kv.0).collect();
+        assert_eq!(b_tree.remove(&2), Some("zwei"));
+        assert_eq!(b_tree.remove(&2), None);
+        assert_eq!(b_tree.get(&2), None);
 
-        assert_eq!(vec, vec![1,2,3,4,5,6,7])
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+
+        let vec: Vec<_> = b_tree.into_iter().map(|kv| kv.0).collect();
 
+        assert_eq!(vec, vec![4, 6])
     }
 
     #[test]
-    fn it_grows() {
-        let mut b_tree = BinaryTree::with_capacity(2);
+    fn remove_root() {
+        let mut b_tree = BinaryTree::with_capacity(8);
 
-        b_tree.insert(7, "sieben");
         b_tree.insert(4, "vier");
         b_tree.insert(2, "zwei");
+        b_tree.insert(6, "sechs");
         b_tree.insert(5, "fünf");
 
-        assert_eq!(b_tree.get(&2), Some("zwei").as_ref());
+        assert_eq!(b_tree.remove(&4), Some("vier"));
         assert_eq!(b_tree.get(&5), Some("fünf").as_ref());
+        assert_eq!(b_tree.get(&6), Some("sechs").as_ref());
+
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+
+        let vec: Vec<_> = b_tree.into_iter().map(|kv| kv.0).collect();
+
+        assert_eq!(vec, vec![2, 5, 6])
     }
 
     #[test]
-    fn iter_it() {
-        let mut b_tree = BinaryTree::with_capacity(32);
+    fn remove_with_one_child() {
+        let mut b_tree = BinaryTree::with_capacity(8);
 
-        b_tree.insert(7, "sieben");
         b_tree.insert(4, "vier");
         b_tree.insert(2, "zwei");
-        b_tree.insert(5, "fünf");
+        b_tree.insert(1, "eins");
+        b_tree.insert(6, "sechs");
+        b_tree.insert(7, "sieben");
+
+        assert_eq!(b_tree.remove(&2), Some("zwei"));
+        assert_eq!(b_tree.remove(&6), Some("sechs"));
+        assert_eq!(b_tree.get(&1), Some("eins").as_ref());
+        assert_eq!(b_tree.get(&7), Some("sieben").as_ref());
+
+        assert_eq!(b_tree.check_invariants(), Ok(()));
 
         let vec: Vec<_> = b_tree.into_iter().map(|kv| kv.0).collect();
 
-        assert_eq!(vec, vec![2, 4, 5, 7])
+        assert_eq!(vec, vec![1, 4, 7])
+    }
+
+    #[test]
+    fn remove_with_two_children() {
+        let mut b_tree = BinaryTree::with_capacity(16);
+
+        for key in [8, 4, 12, 2, 6, 10, 14, 5, 7] {
+            b_tree.insert(key, key * 10);
+        }
+
+        assert_eq!(b_tree.remove(&4), Some(40));
+        assert_eq!(b_tree.get(&5), Some(&50));
+        assert_eq!(b_tree.get(&7), Some(&70));
+        assert_eq!(b_tree.get(&4), None);
+
+        assert_eq!(b_tree.check_invariants(), Ok(()));
+
+        let vec: Vec<_> = b_tree.into_iter().map(|kv| kv.0).collect();
+
+        assert_eq!(vec, vec![2, 5, 6, 7, 8, 10, 12, 14])
     }
 }
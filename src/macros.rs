@@ -0,0 +1,23 @@
+/// Creates a [`BinaryTree`](crate::BinaryTree) from `key => value` pairs.
+///
+/// Reserves room for all pairs up front. Like repeated inserts, a later
+/// duplicate key overwrites an earlier one.
+#[macro_export]
+macro_rules! bitree {
+    (@unit $key:expr) => {
+        ()
+    };
+    () => {
+        $crate::BinaryTree::new()
+    };
+    ($($key:expr => $value:expr),+ $(,)?) => {{
+        let mut tree = $crate::BinaryTree::new();
+
+        tree.reserve(<[()]>::len(&[$($crate::bitree!(@unit $key)),+]));
+        $(
+            tree.insert($key, $value);
+        )+
+
+        tree
+    }};
+}
@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+
+use crate::{BinaryTree, Keys};
+
+/// A BinaryTree that keeps every value inserted under a key, so keys may
+/// repeat.
+///
+/// The values of equal keys are stored together in insertion order in the
+/// same cell, so duplicates always cluster and iterate together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinaryMultiTree<K, V> {
+    tree: BinaryTree<K, Vec<V>>,
+    len: usize,
+}
+
+impl<K: Ord, V> BinaryMultiTree<K, V> {
+    pub fn new() -> Self {
+        Self {
+            tree: BinaryTree::new(),
+            len: 0,
+        }
+    }
+
+    /// The number of values, counting every duplicate key.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds `value` after the values already stored under `key`.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.tree.entry(key).or_default().push(value);
+        self.len += 1;
+    }
+
+    /// All values of `key` in insertion order, empty if the key is absent.
+    pub fn get<Q>(&self, key: &Q) -> &[V]
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.tree.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.tree.contains_key(key)
+    }
+
+    /// Removes `key` with all its values.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<Vec<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let values = self.tree.remove(key)?;
+        self.len -= values.len();
+
+        Some(values)
+    }
+
+    /// Iterates over the distinct keys in order.
+    pub fn keys(&self) -> Keys<'_, K, Vec<V>> {
+        self.tree.keys()
+    }
+
+    /// Iterates over every entry in key order, duplicates in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.tree
+            .iter()
+            .flat_map(|(key, values)| values.iter().map(move |value| (key, value)))
+    }
+}
+
+impl<K: Ord, V> Default for BinaryMultiTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for BinaryMultiTree<K, V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut tree = Self::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for BinaryMultiTree<K, V> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates() {
+        let mut b_tree = BinaryMultiTree::new();
+
+        b_tree.insert(5, "fünf");
+        b_tree.insert(3, "drei");
+        b_tree.insert(5, "five");
+        b_tree.insert(7, "sieben");
+        b_tree.insert(5, "cinq");
+
+        assert_eq!(b_tree.get(&5), ["fünf", "five", "cinq"]);
+        assert_eq!(b_tree.get(&4), [] as [&str; 0]);
+        assert_eq!(b_tree.len(), 5);
+
+        assert!(b_tree.iter().map(|kv| *kv.0).eq([3, 5, 5, 5, 7]));
+        assert!(b_tree.keys().copied().eq([3, 5, 7]));
+
+        assert_eq!(b_tree.remove(&5).map(|values| values.len()), Some(3));
+        assert_eq!(b_tree.len(), 2);
+        assert!(!b_tree.contains_key(&5));
+    }
+
+    #[test]
+    fn collect() {
+        let b_tree: BinaryMultiTree<_, _> = [(2, 'a'), (1, 'b'), (2, 'c')].into_iter().collect();
+
+        assert!(b_tree.iter().eq([(&1, &'b'), (&2, &'a'), (&2, &'c')]));
+    }
+}
@@ -0,0 +1,125 @@
+use alloc::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::{BinaryTree, BALANCED_FITS};
+
+/// Inputs shorter than this are built sequentially, and subtrees with fewer
+/// items are filled by a single task, so the overhead of spawning stays small.
+const PARALLEL_THRESHOLD: usize = 1 << 12;
+
+impl<K, V> BinaryTree<K, V> {
+    /// Builds the same balanced tree as [`from_sorted_slice`](Self::from_sorted_slice),
+    /// filling the cells in parallel.
+    ///
+    /// The cells of a subtree on one level are adjacent, and split in half
+    /// between its two children. So the array is cut into its levels, and
+    /// the two halves of `items` around the median go to separate tasks
+    /// together with the halves of every level below, which keeps the work
+    /// linear. `items` must be sorted and free of duplicate keys.
+    pub fn par_from_sorted_slice(items: &[(K, V)]) -> Self
+    where
+        K: Ord + Clone + Send + Sync,
+        V: Clone + Send + Sync,
+    {
+        if items.len() < PARALLEL_THRESHOLD {
+            return Self::from_sorted_slice(items);
+        }
+
+        debug_assert!(
+            items.par_windows(2).all(|w| w[0].0 < w[1].0),
+            "par_from_sorted_slice requires sorted, unique keys"
+        );
+
+        let mut mem: Vec<_> = (0..Self::balanced_capacity(items.len()))
+            .into_par_iter()
+            .map(|_| None)
+            .collect();
+
+        let mut levels = Vec::new();
+        let mut rest = &mut mem[..];
+
+        for depth in 0..Self::balanced_height(items.len()) {
+            let (level, below) = rest.split_at_mut(1 << depth);
+            levels.push(level);
+            rest = below;
+        }
+
+        par_fill(&mut levels, items);
+
+        let mut tree = Self::new();
+        tree.mem = mem.into_boxed_slice();
+        tree.set_len(items.len());
+
+        tree
+    }
+}
+
+/// Writes `items` balanced into the subtree whose cells on each level are
+/// `levels[depth]`, splitting off a task per child while there is enough
+/// left to do.
+fn par_fill<K, V>(levels: &mut [&mut [Option<(K, V)>]], items: &[(K, V)])
+where
+    K: Clone + Send + Sync,
+    V: Clone + Send + Sync,
+{
+    if items.len() < PARALLEL_THRESHOLD {
+        return fill(levels, 0, 0, items);
+    }
+
+    let (root, below) = levels.split_first_mut().expect(BALANCED_FITS);
+    let left = items.len() / 2;
+
+    root[0] = Some(items[left].clone());
+
+    let (mut left_levels, mut right_levels): (Vec<_>, Vec<_>) = below
+        .iter_mut()
+        .map(|level| level.split_at_mut(level.len() / 2))
+        .unzip();
+
+    rayon::join(
+        || par_fill(&mut left_levels, &items[..left]),
+        || par_fill(&mut right_levels, &items[left + 1..]),
+    );
+}
+
+/// Writes `items` balanced into the subtree rooted at `levels[depth][offset]`,
+/// picking the median as the root like `fill_balanced`.
+fn fill<K: Clone, V: Clone>(
+    levels: &mut [&mut [Option<(K, V)>]],
+    depth: usize,
+    offset: usize,
+    items: &[(K, V)],
+) {
+    if items.is_empty() {
+        return;
+    }
+
+    let left = items.len() / 2;
+
+    levels[depth][offset] = Some(items[left].clone());
+
+    fill(levels, depth + 1, 2 * offset, &items[..left]);
+    fill(levels, depth + 1, 2 * offset + 1, &items[left + 1..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::BinaryTree;
+
+    #[test]
+    fn matches_sequential() {
+        for len in [0, 1, 7, 1000, 4095, 4096, 10_000, 40_000, 65_535, 65_536] {
+            let items: Vec<_> = (0..len).map(|key| (key, key * 3)).collect();
+
+            let sequential = BinaryTree::from_sorted_slice(&items);
+            let parallel = BinaryTree::par_from_sorted_slice(&items);
+
+            assert_eq!(parallel, sequential);
+            assert_eq!(parallel.mem, sequential.mem);
+            assert_eq!(parallel.check_invariants(), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{BinaryTree, Comparator, Index};
+
+/// Serializes the entries as a map in key order, independent of the array layout.
+impl<K: Serialize, V: Serialize, C, I: Index> Serialize for BinaryTree<K, V, C, I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+
+        for (key, value) in self.iter() {
+            map.serialize_entry(key, value)?;
+        }
+
+        map.end()
+    }
+}
+
+/// Deserializes a map into a balanced tree. On duplicate keys the last one wins,
+/// just like repeated inserts.
+impl<'de, K, V, C, I> Deserialize<'de> for BinaryTree<K, V, C, I>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+    C: Comparator<K> + Default,
+    I: Index,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(BinaryTreeVisitor(PhantomData))
+    }
+}
+
+struct BinaryTreeVisitor<K, V, C, I>(PhantomData<(K, V, C, I)>);
+
+impl<'de, K, V, C, I> Visitor<'de> for BinaryTreeVisitor<K, V, C, I>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+    C: Comparator<K> + Default,
+    I: Index,
+{
+    type Value = BinaryTree<K, V, C, I>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut entries: Vec<(K, V)> = Vec::with_capacity(access.size_hint().unwrap_or(0));
+
+        while let Some(entry) = access.next_entry()? {
+            entries.push(entry);
+        }
+
+        let mut tree = BinaryTree::with_capacity_and_comparator(1, C::default());
+
+        // stable, so equal keys keep their incoming order
+        entries.sort_by(|a, b| tree.cmp.compare(&a.0, &b.0));
+
+        let mut unique: Vec<(K, V)> = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            match unique.last_mut() {
+                Some(last) if tree.cmp.compare(&last.0, &entry.0) == Ordering::Equal => {
+                    *last = entry
+                }
+                _ => unique.push(entry),
+            }
+        }
+
+        tree.replace_sorted(unique.len(), unique);
+
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryTree;
+
+    #[test]
+    fn round_trip() {
+        let mut b_tree = BinaryTree::with_capacity(2);
+
+        for key in [7, 4, 2, 5, 9, 8] {
+            b_tree.insert(key, key.to_string());
+        }
+
+        let json = serde_json::to_string(&b_tree).unwrap();
+
+        assert_eq!(json, r#"{"2":"2","4":"4","5":"5","7":"7","8":"8","9":"9"}"#);
+
+        let back: BinaryTree<i32, String> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(back.len(), 6);
+        assert!(back.iter().eq(b_tree.iter()));
+        assert_eq!(back.capacity(), 8);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let b_tree: BinaryTree<i32, i32> = serde_json::from_str(r#"{"3":1,"1":2,"3":3}"#).unwrap();
+
+        assert!(b_tree.into_iter().eq([(1, 2), (3, 3)]));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{BiTree, BinaryTree, Index};
+
+/// A summary of the shape of a BinaryTree, returned by [`BinaryTree::stats`].
+///
+/// Depths count the edges below the root. All fields are 0 for an empty tree.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TreeStats {
+    pub len: usize,
+    pub capacity: usize,
+    /// The number of levels, see [`BinaryTree::height`].
+    pub height: usize,
+    /// The depth of the shallowest leaf.
+    pub min_depth: usize,
+    /// The depth of the deepest entry.
+    pub max_depth: usize,
+    /// The mean depth over all entries.
+    pub avg_depth: f64,
+    /// The fraction of cells that are occupied.
+    pub occupancy_ratio: f64,
+}
+
+impl<K, V, C, I: Index> BinaryTree<K, V, C, I> {
+    /// Collects the [`TreeStats`] of the tree in a single pass over the cells.
+    ///
+    /// A `max_depth` far above `min_depth` hints at a degenerate tree that
+    /// would benefit from [`rebalance`](Self::rebalance).
+    pub fn stats(&self) -> TreeStats {
+        let mut min_depth = None;
+        let mut max_depth = 0;
+        let mut total_depth = 0;
+
+        for index in (0..self.capacity()).filter(|&index| self.is_occupied(index)) {
+            let depth = BiTree::depth(index);
+
+            let is_leaf =
+                self.occupied_left(index).is_none() && self.occupied_right(index).is_none();
+
+            if is_leaf && min_depth.is_none() {
+                min_depth = Some(depth);
+            }
+
+            max_depth = depth;
+            total_depth += depth;
+        }
+
+        let len = self.len();
+
+        TreeStats {
+            len,
+            capacity: self.capacity(),
+            height: if len == 0 { 0 } else { max_depth + 1 },
+            min_depth: min_depth.unwrap_or(0),
+            max_depth,
+            avg_depth: if len == 0 {
+                0.0
+            } else {
+                total_depth as f64 / len as f64
+            },
+            occupancy_ratio: len as f64 / self.capacity() as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryTree;
+
+    #[test]
+    fn balanced() {
+        let mut b_tree = BinaryTree::with_capacity(14);
+
+        for key in [4, 2, 6, 1, 3, 5, 7] {
+            b_tree.insert(key, ());
+        }
+
+        let stats = b_tree.stats();
+
+        assert_eq!((stats.len, stats.capacity, stats.height), (7, 14, 3));
+        assert_eq!((stats.min_depth, stats.max_depth), (2, 2));
+        assert!((stats.avg_depth - 10.0 / 7.0).abs() < 1e-9);
+        assert!((stats.occupancy_ratio - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn skewed() {
+        let mut b_tree = BinaryTree::with_capacity(16);
+
+        for key in [2, 1, 3, 4] {
+            b_tree.insert(key, ());
+        }
+
+        let stats = b_tree.stats();
+
+        assert_eq!((stats.min_depth, stats.max_depth), (1, 2));
+        assert_eq!(stats.height, b_tree.height());
+    }
+
+    #[test]
+    fn empty() {
+        let stats = BinaryTree::<i32, ()>::new().stats();
+
+        assert_eq!((stats.len, stats.height, stats.max_depth), (0, 0, 0));
+        assert_eq!(stats.avg_depth, 0.0);
+    }
+}